@@ -0,0 +1,14 @@
+use std::{fs, str::FromStr};
+
+use arweave_rs::{transaction::Tx, ArweaveSigner};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn verify_transaction(c: &mut Criterion) {
+    let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+    c.bench_function("verify_transaction", |b| {
+        b.iter(|| ArweaveSigner::verify_transaction(black_box(&tx)).unwrap())
+    });
+}
+
+criterion_group!(benches, verify_transaction);
+criterion_main!(benches);
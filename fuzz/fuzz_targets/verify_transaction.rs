@@ -0,0 +1,18 @@
+//! Parses arbitrary bytes as transaction json and verifies the result, as is done with responses
+//! from untrusted gateways. Both steps must return an error on bad input rather than panic.
+//!
+//! Run with `cargo +nightly fuzz run verify_transaction` from the crate root.
+#![no_main]
+
+use std::str::FromStr;
+
+use arweave_rs::{transaction::Tx, ArweaveSigner};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(tx) = Tx::from_str(s) {
+            let _ = ArweaveSigner::verify_transaction(&tx);
+        }
+    }
+});
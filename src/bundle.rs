@@ -0,0 +1,451 @@
+//! [ANS-104](https://github.com/ArweaveTeam/arweave-standards/blob/master/ans/ANS-104.md) data
+//! items and the bundle binary format that packs them into the data of a single transaction.
+
+use crate::{
+    crypto::{
+        base64::Base64,
+        hash::{deep_hash, sha256, DeepHashItem},
+        sign, Provider,
+    },
+    error::Error,
+    transaction::tags::{FromUtf8Strs, Tag},
+};
+
+/// Signature type for Arweave (RSA-PSS 4096) signed data items.
+pub const ARWEAVE_SIGNATURE_TYPE: u16 = 1;
+const SIGNATURE_LENGTH: usize = 512;
+const OWNER_LENGTH: usize = 512;
+const OPTIONAL_FIELD_LENGTH: usize = 32;
+
+/// Value of the `Bundle-Format` tag of a transaction carrying a binary bundle.
+pub const BUNDLE_FORMAT: &str = "binary";
+/// Value of the `Bundle-Version` tag of a transaction carrying a bundle.
+pub const BUNDLE_VERSION: &str = "2.0.0";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataItem {
+    pub signature_type: u16,
+    pub signature: Base64,
+    pub owner: Base64,
+    /// Empty when the item has no target.
+    pub target: Base64,
+    /// Empty when the item has no anchor.
+    pub anchor: Base64,
+    pub tags: Vec<Tag<Base64>>,
+    pub data: Base64,
+}
+
+impl DataItem {
+    pub fn new(
+        target: Base64,
+        anchor: Base64,
+        tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+    ) -> Result<Self, Error> {
+        for (name, field) in [("target", &target), ("anchor", &anchor)] {
+            if !field.is_empty() && field.0.len() != OPTIONAL_FIELD_LENGTH {
+                return Err(Error::InvalidBundle(format!(
+                    "{} must be empty or {} bytes",
+                    name, OPTIONAL_FIELD_LENGTH
+                )));
+            }
+        }
+        Ok(Self {
+            signature_type: ARWEAVE_SIGNATURE_TYPE,
+            target,
+            anchor,
+            tags,
+            data: Base64(data),
+            ..Default::default()
+        })
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signature.0.len() == SIGNATURE_LENGTH && self.owner.0.len() == OWNER_LENGTH
+    }
+
+    /// Id of a signed item: the sha256 hash of its signature.
+    pub fn id(&self) -> Base64 {
+        Base64(sha256(&self.signature.0).to_vec())
+    }
+
+    /// Deep hash item the signature is computed over.
+    pub fn to_deep_hash_item(&self) -> Result<DeepHashItem, Error> {
+        let tags = Tag::to_avro_bytes(&self.tags)?;
+        let signature_type = self.signature_type.to_string();
+        let children = vec![
+            b"dataitem".as_ref(),
+            b"1",
+            signature_type.as_bytes(),
+            &self.owner.0,
+            &self.target.0,
+            &self.anchor.0,
+            &tags,
+            &self.data.0,
+        ]
+        .into_iter()
+        .map(DeepHashItem::from_item)
+        .collect();
+        Ok(DeepHashItem::from_children(children))
+    }
+
+    pub fn sign(&mut self, crypto: &Provider) -> Result<(), Error> {
+        self.signature_type = ARWEAVE_SIGNATURE_TYPE;
+        self.owner = crypto.keypair_modulus();
+        if self.owner.0.len() != OWNER_LENGTH {
+            return Err(Error::InvalidKeypair(
+                "data items must be signed with a 4096 bit key".to_string(),
+            ));
+        }
+        let signature_data = crypto.deep_hash(self.to_deep_hash_item()?);
+        self.signature = crypto.sign(&signature_data)?;
+        Ok(())
+    }
+
+    /// Checks the signature against the item's owner.
+    pub fn verify(&self) -> Result<(), Error> {
+        if !self.is_signed() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let signature_data = deep_hash(self.to_deep_hash_item()?);
+        sign::verify(&self.owner.0, &signature_data, &self.signature.0)
+    }
+
+    /// Binary encoding of the item as it appears in a bundle.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        if !self.is_signed() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let tags = Tag::to_avro_bytes(&self.tags)?;
+
+        let mut bytes = Vec::with_capacity(
+            2 + SIGNATURE_LENGTH + OWNER_LENGTH + 2 + 64 + 16 + tags.len() + self.data.0.len(),
+        );
+        bytes.extend(self.signature_type.to_le_bytes());
+        bytes.extend(&self.signature.0);
+        bytes.extend(&self.owner.0);
+        for field in [&self.target, &self.anchor] {
+            bytes.push(!field.is_empty() as u8);
+            bytes.extend(&field.0);
+        }
+        bytes.extend((self.tags.len() as u64).to_le_bytes());
+        bytes.extend((tags.len() as u64).to_le_bytes());
+        bytes.extend(tags);
+        bytes.extend(&self.data.0);
+        Ok(bytes)
+    }
+
+    /// Decodes an item encoded with [`DataItem::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+        let signature_type = u16::from_le_bytes(reader.take_array()?);
+        if signature_type != ARWEAVE_SIGNATURE_TYPE {
+            return Err(Error::InvalidBundle(format!(
+                "unsupported signature type {}",
+                signature_type
+            )));
+        }
+        let signature = Base64(reader.take(SIGNATURE_LENGTH)?.to_vec());
+        let owner = Base64(reader.take(OWNER_LENGTH)?.to_vec());
+        let mut optional_field = || match reader.take_array::<1>()? {
+            [0] => Ok(Base64::empty()),
+            [1] => Ok(Base64(reader.take(OPTIONAL_FIELD_LENGTH)?.to_vec())),
+            [flag] => Err(Error::InvalidBundle(format!(
+                "invalid presence flag {}",
+                flag
+            ))),
+        };
+        let target = optional_field()?;
+        let anchor = optional_field()?;
+        let tag_count = u64::from_le_bytes(reader.take_array()?);
+        let tags_length = u64::from_le_bytes(reader.take_array()?);
+        let tags_length = usize::try_from(tags_length).map_err(|_| Error::OffsetOverflow)?;
+        let tags = Tag::from_avro_bytes(reader.take(tags_length)?)?;
+        if tags.len() as u64 != tag_count {
+            return Err(Error::InvalidTagEncoding);
+        }
+        Ok(Self {
+            signature_type,
+            signature,
+            owner,
+            target,
+            anchor,
+            tags,
+            data: Base64(reader.0.to_vec()),
+        })
+    }
+}
+
+/// Consumes a byte slice from the front, failing with [`Error::InvalidBundle`] when it runs out.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::InvalidBundle("unexpected end of bytes".to_string()));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    /// A little endian 256 bit number from the bundle header, which must fit a `usize`.
+    fn take_u256(&mut self) -> Result<usize, Error> {
+        let bytes: [u8; 32] = self.take_array()?;
+        if bytes[8..].iter().any(|b| *b != 0) {
+            return Err(Error::OffsetOverflow);
+        }
+        let n = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+        usize::try_from(n).map_err(|_| Error::OffsetOverflow)
+    }
+}
+
+/// Little endian 256 bit encoding of `n`, as used by the bundle header.
+fn to_u256_le_bytes(n: usize) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&(n as u64).to_le_bytes());
+    bytes
+}
+
+/// Assembles signed [`DataItem`]s into bundle bytes: a 32 byte item count, a 64 byte
+/// (size, id) entry per item, then the item binaries in the same order.
+#[derive(Debug, Default)]
+pub struct BundleBuilder {
+    items: Vec<DataItem>,
+}
+
+impl BundleBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_item(mut self, item: DataItem) -> Result<Self, Error> {
+        if !item.is_signed() {
+            return Err(Error::UnsignedTransaction);
+        }
+        self.items.push(item);
+        Ok(self)
+    }
+
+    pub fn build(&self) -> Result<Vec<u8>, Error> {
+        let binaries = self
+            .items
+            .iter()
+            .map(|item| item.to_bytes())
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut bundle = Vec::with_capacity(
+            32 + 64 * binaries.len() + binaries.iter().map(|b| b.len()).sum::<usize>(),
+        );
+        bundle.extend(to_u256_le_bytes(self.items.len()));
+        for (item, binary) in self.items.iter().zip(&binaries) {
+            bundle.extend(to_u256_le_bytes(binary.len()));
+            bundle.extend(item.id().0);
+        }
+        for binary in binaries {
+            bundle.extend(binary);
+        }
+        Ok(bundle)
+    }
+}
+
+/// The signed data items of a bundle.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Bundle {
+    pub items: Vec<DataItem>,
+}
+
+impl Bundle {
+    /// Bundle bytes for `items`, to upload as the data of a transaction tagged with
+    /// [`Bundle::tags`].
+    pub fn from_items(items: Vec<DataItem>) -> Result<Vec<u8>, Error> {
+        items
+            .into_iter()
+            .try_fold(BundleBuilder::new(), BundleBuilder::add_item)?
+            .build()
+    }
+
+    /// Decodes bundle bytes built by [`BundleBuilder`], checking that each item's id matches
+    /// its header entry. Signatures aren't checked; see [`DataItem::verify`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+        let count = reader.take_u256()?;
+        let entries = (0..count)
+            .map(|_| Ok((reader.take_u256()?, reader.take(32)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut items = Vec::with_capacity(count);
+        for (size, id) in entries {
+            let item = DataItem::from_bytes(reader.take(size)?)?;
+            if item.id().0 != id {
+                return Err(Error::InvalidBundle(format!(
+                    "item {} does not match its header id",
+                    item.id()
+                )));
+            }
+            items.push(item);
+        }
+        if !reader.0.is_empty() {
+            return Err(Error::InvalidBundle("trailing bytes".to_string()));
+        }
+        Ok(Self { items })
+    }
+
+    /// Tags that mark a transaction's data as a bundle.
+    pub fn tags() -> Result<Vec<Tag<Base64>>, Error> {
+        Ok(vec![
+            Tag::from_utf8_strs("Bundle-Format", BUNDLE_FORMAT)?,
+            Tag::from_utf8_strs("Bundle-Version", BUNDLE_VERSION)?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use crate::{
+        crypto::{base64::Base64, Provider},
+        error::Error,
+        transaction::tags::{FromUtf8Strs, Tag},
+    };
+
+    use super::{Bundle, BundleBuilder, DataItem};
+
+    #[test]
+    fn test_bundle_layout() -> Result<(), Error> {
+        let crypto =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet_4096.json").unwrap())
+                .unwrap();
+        let mut first = DataItem::new(
+            Base64::empty(),
+            Base64::empty(),
+            vec![Tag::<Base64>::from_utf8_strs("Content-Type", "text/plain")?],
+            b"first".to_vec(),
+        )?;
+        let mut second = DataItem::new(
+            Base64(vec![1; 32]),
+            Base64(vec![2; 32]),
+            vec![],
+            b"second item".to_vec(),
+        )?;
+        assert!(matches!(
+            BundleBuilder::new().add_item(first.clone()),
+            Err(Error::UnsignedTransaction)
+        ));
+
+        first.sign(&crypto)?;
+        second.sign(&crypto)?;
+        let bundle = BundleBuilder::new()
+            .add_item(first.clone())?
+            .add_item(second.clone())?
+            .build()?;
+
+        let first_bytes = first.to_bytes()?;
+        let second_bytes = second.to_bytes()?;
+        // Signature type, signature, owner, presence flags, tag count and tag bytes length.
+        let fixed = 2 + 512 + 512 + 2 + 8 + 8;
+        assert_eq!(first_bytes.len(), fixed + 26 + 5);
+        assert_eq!(second_bytes.len(), fixed + 64 + 11);
+
+        let mut count = [0u8; 32];
+        count[0] = 2;
+        assert_eq!(bundle[..32], count);
+
+        let entries = &bundle[32..32 + 2 * 64];
+        for (entry, (item, bytes)) in entries
+            .chunks(64)
+            .zip([(&first, &first_bytes), (&second, &second_bytes)])
+        {
+            let mut size = [0u8; 32];
+            size[..8].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+            assert_eq!(entry[..32], size);
+            assert_eq!(entry[32..], item.id().0);
+        }
+
+        assert_eq!(bundle[32 + 128..], [first_bytes, second_bytes].concat());
+        Ok(())
+    }
+
+    #[test]
+    fn test_untagged_item() -> Result<(), Error> {
+        let crypto =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet_4096.json").unwrap())
+                .unwrap();
+        let mut item = DataItem::new(Base64::empty(), Base64::empty(), vec![], b"data".to_vec())?;
+        item.sign(&crypto)?;
+        item.verify()?;
+
+        // Zero tags and zero tag bytes, followed directly by the data.
+        let bytes = item.to_bytes()?;
+        let tags_start = 2 + 512 + 512 + 2;
+        assert_eq!(bytes[tags_start..tags_start + 16], [0; 16]);
+        assert_eq!(&bytes[tags_start + 16..], b"data");
+        assert_eq!(DataItem::from_bytes(&bytes)?, item);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_round_trip() -> Result<(), Error> {
+        let crypto =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet_4096.json").unwrap())
+                .unwrap();
+        let items = (0..3u8)
+            .map(|i| {
+                let mut item = DataItem::new(
+                    if i == 1 {
+                        Base64(vec![i; 32])
+                    } else {
+                        Base64::empty()
+                    },
+                    if i == 2 {
+                        Base64(vec![i; 32])
+                    } else {
+                        Base64::empty()
+                    },
+                    vec![Tag::<Base64>::from_utf8_strs("Record", &i.to_string())?],
+                    vec![i; 100 * i as usize],
+                )?;
+                item.sign(&crypto)?;
+                Ok(item)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let bytes = Bundle::from_items(items.clone())?;
+        let bundle = Bundle::from_bytes(&bytes)?;
+        assert_eq!(bundle.items, items);
+        for item in &bundle.items {
+            item.verify()?;
+        }
+
+        let mut tampered = bundle.items[1].clone();
+        tampered.data.0[0] ^= 1;
+        assert!(matches!(tampered.verify(), Err(Error::InvalidSignature)));
+
+        assert!(matches!(
+            Bundle::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidBundle(_))
+        ));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            Bundle::from_bytes(&trailing),
+            Err(Error::InvalidBundle(_))
+        ));
+        let mut wrong_id = bytes;
+        wrong_id[32 + 32] ^= 1;
+        assert!(matches!(
+            Bundle::from_bytes(&wrong_id),
+            Err(Error::InvalidBundle(_))
+        ));
+
+        assert_eq!(
+            Bundle::from_bytes(&Bundle::from_items(vec![])?)?,
+            Bundle::default()
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use pretend::{resolver::UrlResolver, Pretend, Url};
 use pretend_reqwest::Client as HttpClient;
 
+#[allow(dead_code)]
 pub struct Client(Pretend<HttpClient, UrlResolver>);
 
 impl Client {
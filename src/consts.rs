@@ -2,6 +2,15 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const ARWEAVE_BASE_URL: &str = "https://arweave.net/";
 
+/// Price oracle queried for the USD price of AR.
+pub const ORACLE_PRICE_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=arweave&vs_currencies=usd";
+
+/// Prefix applied to messages signed with [`crate::ArweaveSigner::sign`]. Transaction signatures
+/// are computed over a bare 48 byte deep hash, so a prefixed message can never be reinterpreted
+/// as a transaction signature.
+pub const MESSAGE_SIGNING_PREFIX: &[u8] = b"\x19Arweave Signed Message:\n";
+
 /// Block size used for pricing calculations = 256 KB
 pub const BLOCK_SIZE: u64 = 1024 * 256;
 
@@ -12,12 +21,39 @@ pub const MAX_TX_DATA: u64 = 10_000_000;
 /// of simultaneous request to the `chunk/ endpoint`.
 pub const CHUNKS_BUFFER_FACTOR: usize = 20;
 
-/// Number of times to retry posting chunks if not successful.
+/// Buffer argument used for the chunks of transactions posted with `Arweave::post_transaction`,
+/// allowing `DEFAULT_CHUNKS_BUFFER * CHUNKS_BUFFER_FACTOR` chunk requests in flight.
+pub const DEFAULT_CHUNKS_BUFFER: usize = 5;
+
+/// Maximum number of simultaneous requests to the `chunk/{offset}` endpoint when downloading.
+pub const CHUNKS_DOWNLOAD_CONCURRENCY: usize = 10;
+
+/// Number of times to retry posting chunks if not successful, in the default
+/// [`crate::retry::RetryPolicy`].
 pub const CHUNKS_RETRIES: u16 = 10;
 
-/// Number of seconds to wait between retying to post a failed chunk.
+/// Number of seconds to wait between retying to post a failed chunk, in the default
+/// [`crate::retry::RetryPolicy`].
 pub const CHUNKS_RETRY_SLEEP: u64 = 1;
 
+/// Number of times to retry posting a transaction to a gateway whose queue is full, backing off
+/// exponentially from `CHUNKS_RETRY_SLEEP`, before giving up on that gateway.
+pub const QUEUE_FULL_RETRIES: u16 = 3;
+
+/// Number of seconds to wait for a connection to a gateway to be established.
+pub const CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// Number of seconds a request to a gateway may take, from connecting to reading the whole
+/// response, unless overridden with `Arweave::with_timeout`.
+pub const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Number of seconds a fetched block height is reused before fetching it again.
+pub const HEIGHT_CACHE_SECS: u64 = 30;
+
+/// Number of seconds after which a cached block height is no longer trusted for deadline
+/// checks, roughly one block time.
+pub const MAX_HEIGHT_AGE_SECS: u64 = 120;
+
 // First block to use V2 block format
 pub const V2_BLOCK_HEIGHT: u32 = 269510;
 
@@ -1,10 +1,16 @@
 use std::str::FromStr;
 
+use rand::RngCore;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::error::Error;
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
+/// Bytes that serialize as unpadded base64url.
+///
+/// Ordering compares the decoded bytes lexicographically, which gives a stable total order for
+/// keys and sorted output but carries no meaning otherwise. It also differs from the order of
+/// the encoded strings, since the base64url alphabet isn't in ascii order.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Default)]
 pub struct Base64(pub Vec<u8>);
 
 impl std::fmt::Display for Base64 {
@@ -20,10 +26,16 @@ impl From<&[u8]> for Base64 {
     }
 }
 
+/// Accepts base64url with or without padding, the form some tools and gateways use.
 impl FromStr for Base64 {
-    type Err = base64::DecodeError;
+    type Err = Error;
     fn from_str(str: &str) -> Result<Self, Self::Err> {
-        let result = base64::decode_config(str, base64::URL_SAFE_NO_PAD)?;
+        let unpadded = str
+            .strip_suffix("==")
+            .or_else(|| str.strip_suffix('='))
+            .unwrap_or(str);
+        let result = base64::decode_config(unpadded, base64::URL_SAFE_NO_PAD)
+            .map_err(|e| Error::Base64Decode(e.to_string()))?;
         Ok(Self(result))
     }
 }
@@ -37,12 +49,19 @@ impl Base64 {
         Ok(Self(str.as_bytes().to_vec()))
     }
     pub fn to_utf8_string(&self) -> Result<String, Error> {
-        Ok(String::from_utf8(self.0.clone()).expect("Could not convert from utf8"))
+        String::from_utf8(self.0.clone()).map_err(|_| Error::InvalidTagEncoding)
     }
 
     pub fn empty() -> Self {
         Base64(vec![])
     }
+
+    /// `len` bytes from the thread-local CSPRNG, e.g. for data item anchors and nonces.
+    pub fn random(len: usize) -> Self {
+        let mut bytes = vec![0; len];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Base64(bytes)
+    }
 }
 
 impl Serialize for Base64 {
@@ -62,9 +81,7 @@ impl<'de> Deserialize<'de> for Base64 {
             }
 
             fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
-                base64::decode_config(v, base64::URL_SAFE_NO_PAD)
-                    .map(Base64)
-                    .map_err(|_| de::Error::custom("failed to decode base64 string"))
+                Base64::from_str(v).map_err(|_| de::Error::custom("failed to decode base64 string"))
             }
         }
         deserializer.deserialize_str(Vis)
@@ -75,7 +92,33 @@ impl<'de> Deserialize<'de> for Base64 {
 mod tests {
     use std::str::FromStr;
 
-    use crate::crypto::base64::Base64;
+    use proptest::prelude::*;
+
+    use crate::{crypto::base64::Base64, error::Error};
+
+    proptest! {
+        #[test]
+        fn test_string_round_trip(bytes in proptest::collection::vec(any::<u8>(), 0..100)) {
+            let b = Base64(bytes);
+            let string = b.to_string();
+            prop_assert!(!string.contains(['=', '+', '/']), "{}", string);
+            prop_assert_eq!(Base64::from_str(&string).unwrap(), b.clone());
+            let padded = base64::encode_config(&b.0, base64::URL_SAFE);
+            prop_assert_eq!(Base64::from_str(&padded).unwrap(), b);
+        }
+    }
+
+    #[test]
+    fn test_invalid_base64() {
+        for invalid in ["LCws!", "LC+s", "LCwsL", "LC=ws", "LCws==="] {
+            assert!(
+                matches!(Base64::from_str(invalid), Err(Error::Base64Decode(_))),
+                "{invalid}"
+            );
+        }
+        assert_eq!(Base64::from_str("").unwrap(), Base64::empty());
+        assert_eq!(Base64::from_str("LCwsLCwsLA==").unwrap().0, vec![44; 7]);
+    }
 
     #[test]
     fn test_deserialize_base64() {
@@ -88,6 +131,16 @@ mod tests {
         assert_eq!(format!("{}", base_64), "LCwsLCwsLA");
     }
 
+    #[test]
+    fn test_base64_random() {
+        let a = Base64::random(32);
+        let b = Base64::random(32);
+        assert_eq!(a.0.len(), 32);
+        assert_eq!(b.0.len(), 32);
+        assert_ne!(a, b);
+        assert!(Base64::random(0).is_empty());
+    }
+
     #[test]
     fn test_base64_convert_utf8() {
         let foo_b64 = Base64::from_utf8_str("foo").unwrap();
@@ -97,6 +150,34 @@ mod tests {
         assert_eq!(foo_b64.to_utf8_string().unwrap(), "foo".to_string());
     }
 
+    #[test]
+    fn test_base64_byte_order() {
+        let mut ids = vec![
+            Base64(vec![2]),
+            Base64(vec![1, 255]),
+            Base64(vec![]),
+            Base64(vec![1]),
+            Base64(vec![255]),
+            Base64(vec![1, 0]),
+        ];
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec![
+                Base64(vec![]),
+                Base64(vec![1]),
+                Base64(vec![1, 0]),
+                Base64(vec![1, 255]),
+                Base64(vec![2]),
+                Base64(vec![255]),
+            ]
+        );
+
+        // Encoded as "BA" and "0A", so the string order is the other way round.
+        assert!(Base64(vec![4]) < Base64(vec![208]));
+        assert!(Base64(vec![4]).to_string() > Base64(vec![208]).to_string());
+    }
+
     #[test]
     fn test_base64_convert_string() {
         let foo_b64 = Base64::from_str("LCwsLCwsLA").unwrap();
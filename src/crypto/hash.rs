@@ -2,8 +2,6 @@ use sha2::Digest;
 
 use crate::error::Error;
 
-use super::utils::concat_u8_48;
-
 pub fn sha256(message: &[u8]) -> [u8; 32] {
     let mut context = sha2::Sha256::new();
     context.update(message);
@@ -22,24 +20,24 @@ pub fn sha384(message: &[u8]) -> [u8; 48] {
 
 /// Returns a SHA256 hash of the the concatenated SHA256 hashes of a vector of messages.
 pub fn hash_all_sha256(messages: Vec<&[u8]>) -> [u8; 32] {
-    let hash: Vec<u8> = messages
-        .into_iter()
-        .map(sha256)
-        .into_iter()
-        .flatten()
-        .collect();
-    sha256(&hash)
+    let mut context = sha2::Sha256::new();
+    for message in messages {
+        context.update(sha256(message));
+    }
+    let mut result: [u8; 32] = [0; 32];
+    result.copy_from_slice(context.finalize().as_ref());
+    result
 }
 
 /// Returns a SHA384 hash of the the concatenated SHA384 hashes of a vector messages.
 pub fn hash_all_sha384(messages: Vec<&[u8]>) -> [u8; 48] {
-    let hash: Vec<u8> = messages
-        .into_iter()
-        .map(sha384)
-        .into_iter()
-        .flatten()
-        .collect();
-    sha384(&hash)
+    let mut context = sha2::Sha384::new();
+    for message in messages {
+        context.update(sha384(message));
+    }
+    let mut result: [u8; 48] = [0; 48];
+    result.copy_from_slice(context.finalize().as_ref());
+    result
 }
 
 #[derive(Debug)]
@@ -76,7 +74,10 @@ pub fn deep_hash(deep_hash_item: DeepHashItem) -> [u8; 48] {
 
             for child in list.into_iter() {
                 let child_hash = deep_hash(child);
-                hash = sha384(&concat_u8_48(hash, child_hash));
+                let mut context = sha2::Sha384::new();
+                context.update(hash);
+                context.update(child_hash);
+                hash.copy_from_slice(context.finalize().as_ref());
             }
             hash
         }
@@ -1,5 +1,7 @@
 //! Functionality for chunking file data and calculating and verifying root ids.
 
+use std::io::Read;
+
 use crate::error::Error;
 use borsh::BorshDeserialize;
 
@@ -84,44 +86,82 @@ impl Helpers<usize> for usize {
         note
     }
 }
-/// Generates data chunks from which the calculation of root id starts.
-pub fn generate_leaves(data: Vec<u8>) -> Result<Vec<Node>, Error> {
-    let mut data_chunks: Vec<&[u8]> = data.chunks(MAX_CHUNK_SIZE).collect();
+/// Byte ranges `(min_byte_range, max_byte_range)` of the data chunks for `data_size` bytes of
+/// data. Chunks are [`MAX_CHUNK_SIZE`] long, except that a last chunk shorter than
+/// [`MIN_CHUNK_SIZE`] is rebalanced with the one before it, and data ending on a full chunk gets a
+/// trailing empty chunk.
+pub fn chunk_ranges(data_size: usize) -> Result<Vec<(usize, usize)>, Error> {
+    let mut sizes = vec![MAX_CHUNK_SIZE; data_size / MAX_CHUNK_SIZE];
+    if !data_size.is_multiple_of(MAX_CHUNK_SIZE) {
+        sizes.push(data_size % MAX_CHUNK_SIZE);
+    }
 
-    #[allow(unused_assignments)]
-    let mut last_two = Vec::new();
+    if sizes.len() > 1 && sizes[sizes.len() - 1] < MIN_CHUNK_SIZE {
+        let last_two = sizes.pop().unwrap() + sizes.pop().unwrap();
+        let chunk_size = last_two.div_ceil(2);
+        sizes.push(chunk_size);
+        sizes.push(last_two - chunk_size);
+    }
 
-    if data_chunks.len() > 1 && data_chunks.last().unwrap().len() < MIN_CHUNK_SIZE {
-        last_two = data_chunks.split_off(data_chunks.len() - 2).concat();
-        let chunk_size = last_two.len() / 2 + (last_two.len() % 2 != 0) as usize;
-        data_chunks.append(&mut last_two.chunks(chunk_size).collect::<Vec<&[u8]>>());
+    if sizes.last() == Some(&MAX_CHUNK_SIZE) {
+        sizes.push(0);
     }
 
-    if data_chunks.last().unwrap().len() == MAX_CHUNK_SIZE {
-        data_chunks.push(&[]);
+    let mut ranges = Vec::with_capacity(sizes.len());
+    let mut min_byte_range: usize = 0;
+    for size in sizes {
+        let max_byte_range = min_byte_range
+            .checked_add(size)
+            .ok_or(Error::OffsetOverflow)?;
+        ranges.push((min_byte_range, max_byte_range));
+        min_byte_range = max_byte_range;
     }
+    Ok(ranges)
+}
 
+/// Generates data chunks from which the calculation of root id starts.
+pub fn generate_leaves(data: &[u8]) -> Result<Vec<Node>, Error> {
     let mut leaves = Vec::<Node>::new();
-    let mut min_byte_range = 0;
-    for chunk in data_chunks.into_iter() {
-        let data_hash = sha256(chunk);
-        let max_byte_range = min_byte_range + chunk.len();
-        let offset = max_byte_range.to_note_vec();
-        let id = hash_all_sha256(vec![&data_hash, &offset]);
-
-        leaves.push(Node {
-            id,
-            data_hash: Some(data_hash),
-            min_byte_range,
-            max_byte_range,
-            left_child: None,
-            right_child: None,
-        });
-        min_byte_range += chunk.len()
+    for (min_byte_range, max_byte_range) in chunk_ranges(data.len())? {
+        let chunk = data
+            .get(min_byte_range..max_byte_range)
+            .ok_or(Error::SliceError)?;
+        leaves.push(leaf(chunk, min_byte_range, max_byte_range));
     }
     Ok(leaves)
 }
 
+/// Same as [`generate_leaves`] for `data_size` bytes read from `reader`, one chunk at a time.
+pub fn generate_leaves_from_reader<R: Read>(
+    reader: &mut R,
+    data_size: usize,
+) -> Result<Vec<Node>, Error> {
+    let mut leaves = Vec::<Node>::new();
+    let mut chunk = vec![0; MAX_CHUNK_SIZE];
+    for (min_byte_range, max_byte_range) in chunk_ranges(data_size)? {
+        let chunk = &mut chunk[..max_byte_range - min_byte_range];
+        reader
+            .read_exact(chunk)
+            .map_err(|e| Error::IoError(e.to_string()))?;
+        leaves.push(leaf(chunk, min_byte_range, max_byte_range));
+    }
+    Ok(leaves)
+}
+
+fn leaf(chunk: &[u8], min_byte_range: usize, max_byte_range: usize) -> Node {
+    let data_hash = sha256(chunk);
+    let offset = max_byte_range.to_note_vec();
+    let id = hash_all_sha256(vec![&data_hash, &offset]);
+    Node {
+        id,
+        data_hash: Some(data_hash),
+        min_byte_range,
+        max_byte_range,
+        left_child: None,
+        right_child: None,
+    }
+}
+
 /// Hashes together a single branch node from a pair of child nodes.
 pub fn hash_branch(left: Node, right: Node) -> Result<Node, Error> {
     let max_byte_range = left.max_byte_range.to_note_vec();
@@ -138,7 +178,8 @@ pub fn hash_branch(left: Node, right: Node) -> Result<Node, Error> {
 
 /// Builds one layer of branch nodes from a layer of child nodes.
 pub fn build_layer(nodes: Vec<Node>) -> Result<Vec<Node>, Error> {
-    let mut layer = Vec::<Node>::with_capacity(nodes.len() / 2 + (nodes.len() % 2 != 0) as usize);
+    let mut layer =
+        Vec::<Node>::with_capacity(nodes.len() / 2 + !nodes.len().is_multiple_of(2) as usize);
     let mut nodes_iter = nodes.into_iter();
     while let Some(left) = nodes_iter.next() {
         if let Some(right) = nodes_iter.next() {
@@ -178,7 +219,7 @@ pub fn resolve_proofs(node: Node, proof: Option<Proof>) -> Result<Vec<Proof>, Er
             right_child: None,
             ..
         } => {
-            proof.offset = max_byte_range - 1;
+            proof.offset = max_byte_range.checked_sub(1).ok_or(Error::OffsetOverflow)?;
             proof.proof.extend(data_hash);
             proof.proof.extend(max_byte_range.to_note_vec());
             Ok(vec![proof])
@@ -216,6 +257,13 @@ pub fn validate_chunk(
             max_byte_range,
             ..
         } => {
+            if proof.proof.len() < HASH_SIZE + NOTE_SIZE
+                || !(proof.proof.len() - HASH_SIZE - NOTE_SIZE)
+                    .is_multiple_of(HASH_SIZE * 2 + NOTE_SIZE)
+            {
+                return Err(Error::InvalidProof);
+            }
+
             // Split proof into branches and leaf. Leaf is at the end and branches are ordered
             // from root to leaf.
             let (branches, leaf) = proof
@@ -253,7 +301,7 @@ pub fn validate_chunk(
 
             // Validate leaf: both id and data_hash are correct.
             let id = hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()]);
-            if id != root_id && data_hash != leaf_proof.data_hash {
+            if id != root_id || data_hash != leaf_proof.data_hash {
                 return Err(Error::InvalidProof);
             }
         }
@@ -269,6 +317,7 @@ mod tests {
     use crate::crypto::base64::Base64;
 
     use super::*;
+    use proptest::prelude::*;
     use std::str::FromStr;
     use tokio::fs;
 
@@ -278,7 +327,7 @@ mod tests {
     #[tokio::test]
     async fn test_generate_leaves() -> Result<(), Error> {
         let data = fs::read(ONE_MB_BIN).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         assert_eq!(
             leaves[1],
             Node {
@@ -302,7 +351,7 @@ mod tests {
     #[tokio::test]
     async fn test_hash_branch() -> Result<(), Error> {
         let data = fs::read(ONE_MB_BIN).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let mut nodes_iter = leaves.into_iter();
         let left = nodes_iter.next().unwrap();
         let right = nodes_iter.next().unwrap();
@@ -329,7 +378,7 @@ mod tests {
     #[tokio::test]
     async fn test_build_layer() -> Result<(), Error> {
         let data = fs::read(ONE_MB_BIN).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let layer = build_layer(leaves).unwrap();
         assert_eq!(
             layer[0].id,
@@ -351,7 +400,7 @@ mod tests {
             13, 66, 76, 111, 151, 198, 191, 18, 129, 188, 244, 243, 122, 39, 159, 246, 73, 77, 231,
             100, 200, 2, 138, 245, 233, 31, 171, 188, 172, 188, 68, 16,
         ];
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let root = generate_data_root(leaves).unwrap();
         assert_eq!(root.id, root_actual);
         Ok(())
@@ -361,7 +410,7 @@ mod tests {
     async fn test_generate_proof() -> Result<(), Error> {
         let proof_actual = Base64::from_str("7EAC9FsACQRwe4oIzu7Mza9KjgWKT4toYxDYGjWrCdp0QgsrYS6AueMJ_rM6ZEGslGqjUekzD3WSe7B5_fwipgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACAAAnH6dASdQCigcL43lp0QclqBaSncF4TspuvxoFbn2L18EXpQrP1wkbwdIjSSWQQRt_F31yNvxtc09KkPFtzMKAwAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAAAIHiHU9QwOImFzjqSlfxkJJCtSbAox6TbbFhQvlEapSgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAA").unwrap();
         let data = fs::read(REBAR3).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let root = generate_data_root(leaves).unwrap();
 
         let proofs = resolve_proofs(root, None).unwrap();
@@ -377,7 +426,7 @@ mod tests {
     #[tokio::test]
     async fn test_validate_chunks() -> Result<(), Error> {
         let data = fs::read(ONE_MB_BIN).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let root = generate_data_root(leaves.clone()).unwrap();
         let root_id = root.id;
         let proofs = resolve_proofs(root, None).unwrap();
@@ -395,7 +444,7 @@ mod tests {
         let data_root_actual =
             Base64::from_str("t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw").unwrap();
         let data = fs::read(REBAR3).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let root = generate_data_root(leaves).unwrap();
         assert_eq!(root.id.to_vec(), data_root_actual.0);
         Ok(())
@@ -409,7 +458,7 @@ mod tests {
             13, 66, 76, 111, 151, 198, 191, 18, 129, 188, 244, 243, 122, 39, 159, 246, 73, 77, 231,
             100, 200, 2, 138, 245, 233, 31, 171, 188, 172, 188, 68, 16,
         ];
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let root = generate_data_root(leaves).unwrap();
         println!("{:?} {:?}", root.id, &root_actual);
         assert_eq!(root.id, root_actual);
@@ -421,7 +470,7 @@ mod tests {
         let data = vec![0; 256 * 1024 + 1];
         // root id as calculate by arweave-js
         let root_actual = Base64::from_str("br1Vtl3TS_NGWdHmYqBh3-MxrlckoluHCZGmUZk-dJc").unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         let root = generate_data_root(leaves).unwrap();
         println!("{}", Base64(root.id.to_vec()));
         assert_eq!(root.id, root_actual.0.as_ref());
@@ -431,7 +480,7 @@ mod tests {
     #[tokio::test]
     async fn test_even_chunks() -> Result<(), Error> {
         let data = fs::read(ONE_MB_BIN).await.unwrap();
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         println!("{:?}", leaves[4]);
         assert_eq!(leaves.len(), 8);
         Ok(())
@@ -440,9 +489,58 @@ mod tests {
     #[test]
     fn test_small_last_chunk() -> Result<(), Error> {
         let data = vec![0; 256 * 1024 + 1];
-        let leaves: Vec<Node> = generate_leaves(data).unwrap();
+        let leaves: Vec<Node> = generate_leaves(&data).unwrap();
         assert_eq!(131073, leaves[0].max_byte_range);
         assert_eq!(131072, leaves[1].max_byte_range - leaves[1].min_byte_range);
         Ok(())
     }
+
+    fn assert_valid_chunk_ranges(data_size: usize) {
+        let ranges = chunk_ranges(data_size).unwrap();
+        let (&(_, end), chunks) = ranges.split_last().unwrap();
+        assert_eq!(ranges[0].0, 0);
+        assert_eq!(end, data_size);
+        assert!(ranges.windows(2).all(|w| w[0].1 == w[1].0));
+
+        let sizes: Vec<usize> = ranges.iter().map(|(min, max)| max - min).collect();
+        if sizes.last() == Some(&0) {
+            // Data ending on a full chunk gets a trailing empty chunk.
+            assert_eq!(sizes[sizes.len() - 2], MAX_CHUNK_SIZE);
+        } else if !chunks.is_empty() {
+            // All but the last two chunks are full and the last is rebalanced if it's too small.
+            let (full, last_two) = sizes.split_at(sizes.len() - 2);
+            assert!(full.iter().all(|s| *s == MAX_CHUNK_SIZE));
+            assert!(last_two[1] >= MIN_CHUNK_SIZE);
+            assert!(last_two[0] >= last_two[1] && last_two[0] <= MAX_CHUNK_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_chunk_ranges_near_u32_max() {
+        let max = u32::MAX as usize;
+        for data_size in [
+            max - MAX_CHUNK_SIZE,
+            max - 1,
+            max,
+            max + 1,
+            max + MIN_CHUNK_SIZE,
+        ] {
+            assert_valid_chunk_ranges(data_size);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_chunk_ranges_cover_data(data_size in 1usize..(1 << 34)) {
+            assert_valid_chunk_ranges(data_size);
+        }
+
+        #[test]
+        fn test_chunk_ranges_near_chunk_boundaries(
+            chunks in 1usize..64,
+            delta in 0usize..(2 * MIN_CHUNK_SIZE),
+        ) {
+            assert_valid_chunk_ranges(chunks * MAX_CHUNK_SIZE - MIN_CHUNK_SIZE + delta);
+        }
+    }
 }
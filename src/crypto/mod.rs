@@ -1,10 +1,13 @@
 use std::path::PathBuf;
 
+use jsonwebkey::JsonWebKey;
+
 use self::{
     base64::Base64,
     hash::{deep_hash, sha256, DeepHashItem},
     sign::Signer,
 };
+use crate::error::Error;
 
 pub mod base64;
 pub mod hash;
@@ -12,6 +15,9 @@ pub mod merkle;
 pub mod sign;
 pub mod utils;
 
+/// Signs and hashes for a wallet. Signing is the only operation that needs the private key:
+/// a provider created with [`Provider::public_only`] builds and verifies transactions from the
+/// public key alone, so their signature data can be signed elsewhere, e.g. by a KMS or an HSM.
 pub struct Provider {
     pub signer: Box<Signer>,
 }
@@ -25,10 +31,34 @@ impl Default for Provider {
 }
 
 impl Provider {
-    pub fn from_keypair_path(keypair_path: PathBuf) -> Self {
-        let signer = Signer::from_keypair_path(keypair_path)
-            .expect("Could not create signer from keypair_path");
-        Provider::new(Box::new(signer))
+    pub fn from_keypair_path(keypair_path: PathBuf) -> Result<Self, Error> {
+        Ok(Provider::new(Box::new(Signer::from_keypair_path(
+            keypair_path,
+        )?)))
+    }
+
+    /// Creates a provider from the JSON of a JWK, the same format as the keypair files read by
+    /// [`Provider::from_keypair_path`].
+    pub fn from_keypair_bytes(jwk: &[u8]) -> Result<Self, Error> {
+        Ok(Provider::new(Box::new(Signer::from_keypair_bytes(jwk)?)))
+    }
+
+    /// Creates a provider for a brand new wallet. Save it with [`Provider::to_jwk_json`].
+    pub fn generate() -> Result<Self, Error> {
+        Ok(Provider::new(Box::new(Signer::generate()?)))
+    }
+
+    /// The wallet's keypair as JWK JSON, the format read by [`Provider::from_keypair_bytes`].
+    pub fn to_jwk_json(&self) -> Result<String, Error> {
+        self.signer.to_jwk_json()
+    }
+
+    /// Creates a provider for the wallet with public key modulus `modulus` whose private key is
+    /// held elsewhere. Signing with it fails with [`Error::NoPrivateKey`].
+    pub fn public_only(modulus: &Base64) -> Result<Self, Error> {
+        Ok(Provider::new(Box::new(Signer::from_public_key(
+            &modulus.0,
+        )?)))
     }
 
     pub fn new(signer: Box<Signer>) -> Self {
@@ -36,13 +66,23 @@ impl Provider {
     }
 }
 
+impl TryFrom<JsonWebKey> for Provider {
+    type Error = Error;
+
+    fn try_from(jwk: JsonWebKey) -> Result<Self, Self::Error> {
+        Ok(Provider::new(Box::new(Signer::try_from(jwk)?)))
+    }
+}
+
 impl Provider {
     pub fn deep_hash(&self, deep_hash_item: DeepHashItem) -> [u8; 48] {
         deep_hash(deep_hash_item)
     }
 
-    pub fn sign(&self, message: &[u8]) -> Base64 {
-        self.signer.sign(message).expect("Valid message")
+    /// Fails with [`Error::NoPrivateKey`] for a provider created with
+    /// [`Provider::public_only`].
+    pub fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        self.signer.sign(message)
     }
 
     pub fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
@@ -70,6 +110,10 @@ impl Provider {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
+    use jsonwebkey::JsonWebKey;
+
     use super::{base64::Base64, Provider};
 
     #[test]
@@ -83,8 +127,34 @@ mod tests {
             .to_vec(),
         );
         let provider = Provider::default();
-        let signature = provider.sign(&message.0);
+        let signature = provider.sign(&message.0).unwrap();
         let pubk = provider.public_key();
         assert!(provider.verify(&pubk.0, &message.0, &signature.0))
     }
+
+    #[test]
+    fn test_public_only() {
+        let provider = Provider::default();
+        let public = Provider::public_only(&provider.keypair_modulus()).unwrap();
+
+        assert_eq!(public.wallet_address(), provider.wallet_address());
+        assert_eq!(public.keypair_modulus(), provider.keypair_modulus());
+        assert!(matches!(
+            public.sign(b"message"),
+            Err(crate::error::Error::NoPrivateKey)
+        ));
+    }
+
+    #[test]
+    fn test_try_from_jwk() {
+        let jwk: JsonWebKey = fs::read_to_string("res/test_wallet.json")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let provider = Provider::try_from(jwk).unwrap();
+        assert_eq!(
+            provider.wallet_address().to_string(),
+            "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4"
+        );
+    }
 }
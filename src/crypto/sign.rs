@@ -1,21 +1,104 @@
 //! Functionality for creating and verifying signatures and hashing.
 
 use crate::error::Error;
-use data_encoding::BASE64URL;
 use jsonwebkey as jwk;
 use rand::thread_rng;
 use rsa::{
-    pkcs8::{FromPrivateKey, FromPublicKey},
-    PaddingScheme, PublicKey, PublicKeyParts, RsaPrivateKey, RsaPublicKey,
+    pkcs1::{ToRsaPrivateKey, UIntBytes},
+    pkcs8::FromPrivateKey,
+    BigUint, PaddingScheme, PublicKey, PublicKeyParts, RsaPrivateKey, RsaPublicKey,
 };
+use serde::Deserialize;
 use sha2::Digest;
 use std::{fs, path::PathBuf, str::FromStr};
 
 use super::base64::Base64;
 
-/// Struct for for crypto methods.
+/// Size of the keys Arweave wallets use.
+const WALLET_KEY_BITS: usize = 4096;
+
+/// Smallest key accepted from a keypair file. Wallets are [`WALLET_KEY_BITS`], smaller keys are
+/// accepted for test wallets.
+const MIN_KEY_BITS: usize = 2048;
+
+/// The RSA fields of a keypair file. Any other fields, such as the `ext`, `alg` and `key_ops`
+/// that WebCrypto exports add, are ignored.
+#[derive(Deserialize)]
+struct KeyFile {
+    kty: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    d: Option<String>,
+    p: Option<String>,
+    q: Option<String>,
+    dp: Option<String>,
+    dq: Option<String>,
+    qi: Option<String>,
+}
+
+impl KeyFile {
+    fn field(name: &str, value: &Option<String>) -> Result<BigUint, Error> {
+        let value = value
+            .as_deref()
+            .ok_or_else(|| Error::InvalidKeypair(format!("missing field `{}`", name)))?;
+        let bytes = Base64::from_str(value).map_err(|e| {
+            Error::InvalidKeypair(format!("field `{}` is not base64url: {}", name, e))
+        })?;
+        Ok(BigUint::from_bytes_be(&bytes.0))
+    }
+
+    fn into_private_key(self) -> Result<RsaPrivateKey, Error> {
+        match self.kty.as_deref() {
+            Some("RSA") => {}
+            Some(kty) => {
+                return Err(Error::InvalidKeypair(format!(
+                    "key type is `{}`, expected `RSA`",
+                    kty
+                )))
+            }
+            None => return Err(Error::InvalidKeypair("missing field `kty`".to_string())),
+        }
+        let n = Self::field("n", &self.n)?;
+        let e = Self::field("e", &self.e)?;
+        let d = Self::field("d", &self.d)?;
+        let p = Self::field("p", &self.p)?;
+        let q = Self::field("q", &self.q)?;
+        let dp = Self::field("dp", &self.dp)?;
+        let dq = Self::field("dq", &self.dq)?;
+        let qi = Self::field("qi", &self.qi)?;
+
+        if n.bits() < MIN_KEY_BITS {
+            return Err(Error::InvalidKeypair(format!(
+                "key is {} bits, expected at least {}",
+                n.bits(),
+                MIN_KEY_BITS
+            )));
+        }
+        let one = BigUint::from(1u32);
+        if p <= one || q <= one || &p * &q != n {
+            return Err(Error::InvalidKeypair(
+                "`p` and `q` are not the factors of `n`".to_string(),
+            ));
+        }
+        if dp != &d % (&p - &one) || dq != &d % (&q - &one) || (&qi * &q) % &p != one {
+            return Err(Error::InvalidKeypair(
+                "`dp`, `dq` and `qi` don't match `d`, `p` and `q`".to_string(),
+            ));
+        }
+
+        let key = RsaPrivateKey::from_components(n, e, d, vec![p, q]);
+        key.validate()
+            .map_err(|e| Error::InvalidKeypair(format!("inconsistent key: {}", e)))?;
+        Ok(key)
+    }
+}
+
+/// Struct for for crypto methods. Only [`Signer::sign`] and the JWK export need the private key,
+/// so a signer created with [`Signer::from_public_key`] can still build and verify
+/// transactions.
 pub struct Signer {
-    priv_key: RsaPrivateKey,
+    priv_key: Option<RsaPrivateKey>,
+    pub_key: RsaPublicKey,
 }
 
 impl Default for Signer {
@@ -27,29 +110,92 @@ impl Default for Signer {
 
 impl Signer {
     fn new(priv_key: RsaPrivateKey) -> Self {
-        Self { priv_key }
+        Self {
+            pub_key: priv_key.to_public_key(),
+            priv_key: Some(priv_key),
+        }
     }
 
-    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Self {
-        let pem = jwk.key.to_pem();
-        let priv_key = RsaPrivateKey::from_pkcs8_pem(&pem).unwrap();
+    /// Creates a signer that only holds the public key with modulus `modulus`, the owner of
+    /// transactions signed by it, for keys held by a remote signer or a hardware module.
+    pub fn from_public_key(modulus: &[u8]) -> Result<Self, Error> {
+        let pub_key = RsaPublicKey::new(BigUint::from_bytes_be(modulus), BigUint::from(65537u32))
+            .map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+        Ok(Self {
+            priv_key: None,
+            pub_key,
+        })
+    }
+
+    pub fn has_private_key(&self) -> bool {
+        self.priv_key.is_some()
+    }
 
-        Self::new(priv_key)
+    fn private_key(&self) -> Result<&RsaPrivateKey, Error> {
+        self.priv_key.as_ref().ok_or(Error::NoPrivateKey)
+    }
+
+    pub fn from_jwk(jwk: jwk::JsonWebKey) -> Self {
+        Self::try_from(jwk).expect("Could not create signer from jwk")
     }
 
     pub fn from_keypair_path(keypair_path: PathBuf) -> Result<Self, Error> {
-        let data = fs::read_to_string(keypair_path).expect("Could not open file");
-        let jwk_parsed: jwk::JsonWebKey = data.parse().expect("Could not parse key");
+        let data = fs::read(keypair_path).map_err(|e| Error::IoError(e.to_string()))?;
+        Self::from_keypair_bytes(&data)
+    }
 
-        Ok(Self::from_jwk(jwk_parsed))
+    /// Creates a signer from the JSON of a JWK, the same format as the keypair files read by
+    /// [`Signer::from_keypair_path`]. Fields other than the RSA key's are ignored, so files
+    /// exported by arweave-js or other wallet tools load as well.
+    pub fn from_keypair_bytes(jwk: &[u8]) -> Result<Self, Error> {
+        let key_file: KeyFile =
+            serde_json::from_slice(jwk).map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+        Ok(Self::new(key_file.into_private_key()?))
+    }
+
+    /// Creates a signer with a freshly generated 4096 bit key, the size of Arweave wallets.
+    pub fn generate() -> Result<Self, Error> {
+        let priv_key = RsaPrivateKey::new(&mut thread_rng(), WALLET_KEY_BITS)
+            .map_err(|e| Error::CryptoError(e.to_string()))?;
+        Ok(Self::new(priv_key))
+    }
+
+    /// The private key as a JWK, the format of Arweave keypair files.
+    pub fn to_jwk(&self) -> Result<jwk::JsonWebKey, Error> {
+        let der = self
+            .private_key()?
+            .to_pkcs1_der()
+            .map_err(|e| Error::CryptoError(e.to_string()))?;
+        let key = der.private_key();
+        let bytes = |n: UIntBytes| jwk::ByteVec::from(n.as_bytes());
+        Ok(jwk::JsonWebKey::new(jwk::Key::RSA {
+            public: jwk::RsaPublic {
+                e: jwk::PublicExponent,
+                n: bytes(key.modulus),
+            },
+            private: Some(jwk::RsaPrivate {
+                d: bytes(key.private_exponent),
+                p: Some(bytes(key.prime1)),
+                q: Some(bytes(key.prime2)),
+                dp: Some(bytes(key.exponent1)),
+                dq: Some(bytes(key.exponent2)),
+                qi: Some(bytes(key.coefficient)),
+            }),
+        }))
+    }
+
+    /// The private key as the JSON of a JWK, to be saved as a keypair file and read back with
+    /// [`Signer::from_keypair_path`].
+    pub fn to_jwk_json(&self) -> Result<String, Error> {
+        serde_json::to_string(&self.to_jwk()?).map_err(|e| Error::CryptoError(e.to_string()))
     }
 
     pub fn public_key(&self) -> Base64 {
-        Base64(self.priv_key.to_public_key().n().to_bytes_be())
+        Base64(self.pub_key.n().to_bytes_be())
     }
 
     pub fn keypair_modulus(&self) -> Result<Base64, Error> {
-        let modulus = self.priv_key.to_public_key().n().to_bytes_be();
+        let modulus = self.pub_key.n().to_bytes_be();
         Ok(Base64(modulus.to_vec()))
     }
 
@@ -62,7 +208,7 @@ impl Signer {
 
     pub fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
         let mut hasher = sha2::Sha256::new();
-        hasher.update(&message);
+        hasher.update(message);
         let hashed = hasher.finalize();
 
         let rng = thread_rng();
@@ -73,41 +219,68 @@ impl Signer {
         };
 
         let signature = self
-            .priv_key
-            .sign(padding, &hashed.as_ref())
+            .private_key()?
+            .sign(padding, hashed.as_ref())
             .map_err(|e| Error::SigningError(e.to_string()))?;
 
         Ok(Base64(signature))
     }
 
     pub fn verify(&self, pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
-        let jwt_str = format!(
-            "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-            BASE64URL.encode(pub_key)
-        );
-        let jwk: jwk::JsonWebKey = jwt_str.parse().unwrap();
+        verify(pub_key, message, signature)
+    }
+}
 
-        let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(&message);
-        let hashed = &hasher.finalize();
+/// Verifies an RSA-PSS signature over `message` made by the key with modulus `pub_key` and the
+/// public exponent used by Arweave wallets (65537).
+pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
+    let pub_key = RsaPublicKey::new(BigUint::from_bytes_be(pub_key), BigUint::from(65537u32))
+        .map_err(|_| Error::InvalidSignature)?;
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(message);
+    let hashed = &hasher.finalize();
 
-        let rng = thread_rng();
-        let padding = PaddingScheme::PSS {
-            salt_rng: Box::new(rng),
-            digest: Box::new(sha2::Sha256::new()),
-            salt_len: None,
-        };
-        pub_key
-            .verify(padding, hashed.as_ref(), signature)
-            .map(|_| ())
-            .map_err(|_| Error::InvalidSignature)
+    let rng = thread_rng();
+    let padding = PaddingScheme::PSS {
+        salt_rng: Box::new(rng),
+        digest: Box::new(sha2::Sha256::new()),
+        salt_len: None,
+    };
+    pub_key
+        .verify(padding, hashed.as_ref(), signature)
+        .map(|_| ())
+        .map_err(|_| Error::InvalidSignature)
+}
+
+impl TryFrom<jwk::JsonWebKey> for Signer {
+    type Error = Error;
+
+    fn try_from(jwk: jwk::JsonWebKey) -> Result<Self, Self::Error> {
+        if !matches!(
+            *jwk.key,
+            jwk::Key::RSA {
+                private: Some(_),
+                ..
+            }
+        ) {
+            return Err(Error::InvalidKeypair("not an RSA private key".to_string()));
+        }
+        let pem = jwk
+            .key
+            .try_to_pem()
+            .map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+        let priv_key = RsaPrivateKey::from_pkcs8_pem(&pem)
+            .map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+
+        Ok(Self::new(priv_key))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{path::PathBuf, str::FromStr};
+    use std::{fs, path::PathBuf, str::FromStr};
+
+    use jsonwebkey::JsonWebKey;
 
     use crate::{
         crypto::{base64::Base64, sign::Signer},
@@ -145,4 +318,148 @@ mod tests {
         //provider.verify(&pubk.0, &message.0, &signature.0)
         Ok(())
     }
+
+    #[test]
+    fn test_try_from_public_jwk() {
+        let jwk: JsonWebKey = fs::read_to_string("res/test_wallet.json")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let public_jwk = JsonWebKey::new(jwk.key.to_public().unwrap().into_owned());
+        assert!(matches!(
+            Signer::try_from(public_jwk),
+            Err(error::Error::InvalidKeypair(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_public_key() -> Result<(), error::Error> {
+        let signer = Signer::default();
+        let public = Signer::from_public_key(&signer.keypair_modulus()?.0)?;
+
+        assert!(signer.has_private_key());
+        assert!(!public.has_private_key());
+        assert_eq!(public.wallet_address()?, signer.wallet_address()?);
+        assert_eq!(public.public_key(), signer.public_key());
+        assert!(matches!(
+            public.sign(b"message"),
+            Err(error::Error::NoPrivateKey)
+        ));
+        assert!(matches!(
+            public.to_jwk_json(),
+            Err(error::Error::NoPrivateKey)
+        ));
+
+        let signature = signer.sign(b"message")?;
+        public.verify(&public.public_key().0, b"message", &signature.0)
+    }
+
+    #[test]
+    fn test_from_keypair_bytes() {
+        let jwk = fs::read("res/test_wallet.json").unwrap();
+        let signer = Signer::from_keypair_bytes(&jwk).unwrap();
+        assert_eq!(
+            signer.wallet_address().unwrap().to_string(),
+            "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4"
+        );
+
+        for invalid in [&b"not json"[..], b"{}", &[0xff, 0xfe]] {
+            assert!(matches!(
+                Signer::from_keypair_bytes(invalid),
+                Err(error::Error::InvalidKeypair(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_webcrypto_export() {
+        let exported = Signer::from_keypair_path("res/test_wallet_webcrypto.json".into()).unwrap();
+        let wallet = Signer::from_keypair_path("res/test_wallet_4096.json".into()).unwrap();
+        assert_eq!(
+            exported.wallet_address().unwrap(),
+            wallet.wallet_address().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_keypair_reasons() {
+        let wallet: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string("res/test_wallet.json").unwrap()).unwrap();
+        let other: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string("res/test_wallet_4096.json").unwrap())
+                .unwrap();
+        let with = |field: &str, value: serde_json::Value| {
+            let mut wallet = wallet.clone();
+            match value {
+                serde_json::Value::Null => wallet.as_object_mut().unwrap().remove(field),
+                value => wallet
+                    .as_object_mut()
+                    .unwrap()
+                    .insert(field.to_string(), value),
+            };
+            wallet
+        };
+        let n = wallet["n"].as_str().unwrap();
+
+        for (jwk, reason) in [
+            (with("d", serde_json::Value::Null), "missing field `d`"),
+            (with("qi", serde_json::Value::Null), "missing field `qi`"),
+            (with("kty", "EC".into()), "key type is `EC`"),
+            (with("n", "!!".into()), "field `n` is not base64url"),
+            (
+                with("n", n[..200].into()),
+                "key is 1200 bits, expected at least 2048",
+            ),
+            (with("p", other["p"].clone()), "are not the factors of `n`"),
+            (
+                with("dq", other["dq"].clone()),
+                "don't match `d`, `p` and `q`",
+            ),
+            (
+                with("d", other["d"].clone()),
+                "don't match `d`, `p` and `q`",
+            ),
+        ] {
+            let bytes = serde_json::to_vec(&jwk).unwrap();
+            match Signer::from_keypair_bytes(&bytes) {
+                Err(error::Error::InvalidKeypair(message)) => {
+                    assert!(message.contains(reason), "{message}")
+                }
+                _ => panic!("expected InvalidKeypair for {reason}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate() -> Result<(), error::Error> {
+        let signer = Signer::generate()?;
+        assert_eq!(signer.keypair_modulus()?.0.len(), 512);
+
+        let message = b"hello arweave";
+        let signature = signer.sign(message)?;
+        signer.verify(&signer.public_key().0, message, &signature.0)?;
+
+        let json = signer.to_jwk_json()?;
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for field in ["n", "d", "p", "q", "dp", "dq", "qi"] {
+            assert!(value[field].is_string(), "{}", field);
+        }
+        assert_eq!(value["kty"], "RSA");
+        assert_eq!(value["e"], "AQAB");
+
+        let loaded = Signer::from_keypair_bytes(json.as_bytes())?;
+        assert_eq!(loaded.wallet_address()?, signer.wallet_address()?);
+        loaded.verify(&signer.public_key().0, message, &signature.0)
+    }
+
+    #[test]
+    fn test_to_jwk_round_trips_wallet_file() -> Result<(), error::Error> {
+        let jwk: JsonWebKey = fs::read_to_string("res/test_wallet.json")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let signer = Signer::try_from(jwk.clone())?;
+        assert_eq!(signer.to_jwk()?.key, jwk.key);
+        Ok(())
+    }
 }
@@ -8,6 +8,76 @@ use crate::error::Error;
 /// Winstons are a sub unit of the native Arweave network token, AR. There are 10<sup>12</sup> Winstons per AR.
 pub const WINSTONS_PER_AR: u64 = 1_000_000_000_000;
 
+/// An amount denominated in winstons. Backed by a `u128` since the total supply of AR does
+/// not fit into a `u64` worth of winstons.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize)]
+pub struct Winston(pub u128);
+
+/// Total supply of AR.
+pub const MAX_SUPPLY_AR: u64 = 66_000_000;
+
+impl Winston {
+    /// Converts to AR. Floating point, so only suitable for display and estimates.
+    pub fn to_ar(&self) -> f64 {
+        self.0 as f64 / WINSTONS_PER_AR as f64
+    }
+
+    /// Converts from AR, rounding to the nearest winston. Negative and NaN amounts become
+    /// zero. Above about 9000 AR `f64` can't represent every winston, so use integer winstons
+    /// where exact amounts matter.
+    pub fn from_ar(ar: f64) -> Winston {
+        Winston((ar * WINSTONS_PER_AR as f64).round() as u128)
+    }
+
+    /// The amount in AR with all 12 decimals, e.g. `0.000000600912 AR`. Exact, unlike
+    /// [`Winston::to_ar`].
+    pub fn to_ar_string(&self) -> String {
+        let per_ar = WINSTONS_PER_AR as u128;
+        format!("{}.{:012} AR", self.0 / per_ar, self.0 % per_ar)
+    }
+
+    pub fn checked_add(self, rhs: Winston) -> Option<Winston> {
+        self.0.checked_add(rhs.0).map(Winston)
+    }
+
+    pub fn checked_sub(self, rhs: Winston) -> Option<Winston> {
+        self.0.checked_sub(rhs.0).map(Winston)
+    }
+}
+
+/// Shows the amount in winston, e.g. `600912 winston`; see [`Winston::to_ar_string`] for AR.
+impl std::fmt::Display for Winston {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} winston", self.0)
+    }
+}
+
+impl From<u64> for Winston {
+    fn from(u: u64) -> Self {
+        Winston(u as u128)
+    }
+}
+
+impl From<u128> for Winston {
+    fn from(u: u128) -> Self {
+        Winston(u)
+    }
+}
+
+impl From<Winston> for u128 {
+    fn from(w: Winston) -> Self {
+        w.0
+    }
+}
+
+impl TryFrom<Winston> for u64 {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(w: Winston) -> Result<Self, Self::Error> {
+        u64::try_from(w.0)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct Currency {
     arweave: u64, //integer
@@ -31,40 +101,54 @@ impl From<u128> for Currency {
     }
 }
 
+impl From<Currency> for Winston {
+    fn from(c: Currency) -> Self {
+        Winston(c.arweave as u128 * WINSTONS_PER_AR as u128 + c.winston as u128)
+    }
+}
+
 impl FromStr for Currency {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidAmount(s.to_string());
         let split: Vec<&str> = s.split('.').collect();
         if split.len() == 2 {
+            // The fraction is in AR, so shorter ones are padded to the 12 decimals of a winston.
+            let fraction = split[1];
+            if fraction.is_empty()
+                || fraction.len() > 12
+                || !fraction.bytes().all(|b| b.is_ascii_digit())
+            {
+                return Err(invalid());
+            }
             Ok(Currency {
-                arweave: split[0]
-                    .parse::<u64>()
-                    .expect("Could not parse arweave value"),
-                winston: split[1]
+                arweave: split[0].parse::<u64>().map_err(|_| invalid())?,
+                winston: format!("{:0<12}", fraction)
                     .parse::<u64>()
-                    .expect("Could not parse winston value"),
+                    .map_err(|_| invalid())?,
             })
+        } else if split.len() == 1 {
+            let winstons = split[0].parse::<u128>().map_err(|_| invalid())?;
+            if winstons / WINSTONS_PER_AR as u128 > u64::MAX as u128 {
+                return Err(invalid());
+            }
+            Ok(Currency::from(winstons))
         } else {
-            Ok(Currency {
-                winston: split[0]
-                    .parse::<u64>()
-                    .expect("Could not parse winston value"),
-                ..Currency::default()
-            })
+            Err(invalid())
         }
     }
 }
 
-impl ToString for Currency {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let decimal = format!("{:#012}", self.winston);
         if self.arweave == 0 && self.winston == 0 {
-            '0'.to_string()
+            write!(f, "0")
         } else if self.arweave == 0 {
-            decimal.trim_start_matches('0').to_string()
+            write!(f, "{}", decimal.trim_start_matches('0'))
         } else {
-            self.arweave.to_string() + &decimal
+            write!(f, "{}{}", self.arweave, decimal)
         }
     }
 }
@@ -75,9 +159,9 @@ impl<'de> Deserialize<'de> for Currency {
         D: Deserializer<'de>,
     {
         Ok(match Value::deserialize(deserializer)? {
-            Value::String(s) => Currency::from_str(&s).expect("Could not deserialize"),
+            Value::String(s) => Currency::from_str(&s).map_err(de::Error::custom)?,
             Value::Number(num) => {
-                Currency::from(num.as_u64().expect("Could not deserialize") as u128)
+                Currency::from(num.as_u64().ok_or_else(|| de::Error::custom("Not a u64"))? as u128)
             }
             _ => return Err(de::Error::custom("Wrong type")),
         })
@@ -88,7 +172,15 @@ impl<'de> Deserialize<'de> for Currency {
 mod tests {
     use std::str::FromStr;
 
-    use super::Currency;
+    use super::{Currency, Winston, MAX_SUPPLY_AR, WINSTONS_PER_AR};
+
+    #[test]
+    fn test_str_parse_winstons_above_one_ar() {
+        let curr = Currency::from_str("25000000000000000000").unwrap();
+        assert_eq!(curr, Currency::from(25_000_000_000_000_000_000u128));
+        assert_eq!(curr.arweave, 25_000_000);
+        assert_eq!(curr.to_string(), "25000000000000000000");
+    }
 
     #[test]
     fn test_str_parse() {
@@ -123,6 +215,32 @@ mod tests {
         assert_eq!(curr.to_string(), "10000");
     }
 
+    #[test]
+    fn test_str_parse_short_fraction() {
+        let curr = Currency::from_str("1.5").unwrap();
+        assert_eq!(Winston::from(curr), Winston(1_500_000_000_000));
+        assert_eq!(curr.to_string(), "1500000000000");
+        let curr = Currency::from_str("0.000001").unwrap();
+        assert_eq!(Winston::from(curr), Winston(1_000_000));
+    }
+
+    #[test]
+    fn test_str_parse_invalid() {
+        for s in [
+            "",
+            "ar",
+            "1.5.0",
+            "-1",
+            "1.x",
+            "1.",
+            "1.+5",
+            "1.0000000000001",
+            "340282366920938463463374607431768211455",
+        ] {
+            assert!(Currency::from_str(s).is_err(), "{}", s);
+        }
+    }
+
     #[test]
     fn test_u64_format() {
         let curr = Currency::from(1_000_000_000_000);
@@ -155,4 +273,51 @@ mod tests {
         assert_eq!(curr.arweave, 0);
         assert_eq!(curr.to_string(), "10000");
     }
+
+    #[test]
+    fn test_winston_arithmetic() {
+        let fee = Winston::from(600_912u64);
+        let quantity = Winston::from(100_000u64);
+        assert_eq!(fee.checked_add(quantity), Some(Winston(700_912)));
+        assert_eq!(fee.checked_sub(quantity), Some(Winston(500_912)));
+        assert_eq!(quantity.checked_sub(fee), None);
+        assert_eq!(Winston(u128::MAX).checked_add(Winston(1)), None);
+
+        assert_eq!(u64::try_from(fee), Ok(600_912));
+        assert!(u64::try_from(Winston(u64::MAX as u128 + 1)).is_err());
+        assert_eq!(fee.to_string(), "600912 winston");
+    }
+
+    #[test]
+    fn test_winston_to_ar() {
+        assert_eq!(Winston::from(WINSTONS_PER_AR).to_ar(), 1.0);
+        assert_eq!(Winston(1_500_000_000_000).to_ar(), 1.5);
+        assert_eq!(Winston(0).to_ar(), 0.0);
+    }
+
+    #[test]
+    fn test_winston_from_ar() {
+        assert_eq!(Winston::from_ar(1.0), Winston(WINSTONS_PER_AR as u128));
+        assert_eq!(Winston::from_ar(0.1), Winston(100_000_000_000));
+        assert_eq!(Winston::from_ar(0.000000000001), Winston(1));
+        assert_eq!(Winston::from_ar(1.23), Winston(1_230_000_000_000));
+        assert_eq!(Winston::from_ar(-1.0), Winston(0));
+        assert_eq!(Winston::from_ar(f64::NAN), Winston(0));
+        assert_eq!(Winston::from_ar(0.1).to_ar_string(), "0.100000000000 AR");
+        assert_eq!(Winston(1).to_ar_string(), "0.000000000001 AR");
+        assert_eq!(Winston(0).to_ar_string(), "0.000000000000 AR");
+    }
+
+    #[test]
+    fn test_winston_max_supply() {
+        let max_supply = Winston(MAX_SUPPLY_AR as u128 * WINSTONS_PER_AR as u128);
+        assert!(u64::try_from(max_supply).is_err());
+        assert_eq!(Winston::from_ar(MAX_SUPPLY_AR as f64), max_supply);
+        assert_eq!(max_supply.to_ar(), MAX_SUPPLY_AR as f64);
+        assert_eq!(max_supply.to_ar_string(), "66000000.000000000000 AR");
+        assert_eq!(
+            Winston(max_supply.0 - 1).to_ar_string(),
+            "65999999.999999999999 AR"
+        );
+    }
 }
@@ -1,32 +1,56 @@
 use serde::Deserialize;
 use thiserror::Error;
 
+use crate::currency::Winston;
+
 #[derive(Debug, Error, Deserialize)]
 pub enum Error {
     #[error("Error getting oracle price: {0}")]
     OracleGetPriceError(String),
 
+    #[error("Price oracle unavailable: {0}")]
+    OracleUnavailable(String),
+
     #[error("Getting Arweave price from oracle: {0}")]
     GetPriceError(String),
 
-    #[error("Status code not Ok")]
-    StatusCodeNotOk,
+    #[error("Status code {status} not Ok; {}", status_code_hint(*.status, .body))]
+    StatusCodeNotOk { status: u16, body: String },
+
+    #[error("Json error: {0}")]
+    #[serde(skip)]
+    Serde(#[from] serde_json::Error),
 
-    #[error("Unsigned transaction")]
+    #[error(
+        "Unsigned transaction; sign it with `Arweave::sign_transaction` before posting or \
+         verifying it"
+    )]
     UnsignedTransaction,
 
-    #[error("Invalid proof")]
+    #[error(
+        "Invalid proof; the chunk and its data path don't match the data root, regenerate the \
+         chunks from the original data"
+    )]
     InvalidProof,
 
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Error posting transaction: {0}")]
+    ArweavePostError(String),
+
     #[error("Slice error")]
     SliceError,
 
-    #[error("Invalid tag encoding.")]
+    #[error("Invalid value for transaction.")]
     InvalidValueForTx,
 
     #[error("Invalid tag encoding.")]
     InvalidTagEncoding,
 
+    #[error("Invalid base64url: {0}")]
+    Base64Decode(String),
+
     #[error("Error getting network info: {0}")]
     NetworkInfoError(String),
 
@@ -39,18 +63,248 @@ pub enum Error {
     #[error("Error getting transaction info: {0}")]
     TransactionInfoError(String),
 
+    #[error("Could not get a transaction anchor from the gateway: {0}")]
+    AnchorUnavailable(String),
+
+    #[error(
+        "Transaction anchor expired ({0}); create and sign the transaction again with a fresh \
+         anchor, or post it with `Arweave::post_transaction_reanchoring`"
+    )]
+    AnchorExpired(String),
+
+    #[error("Gateway returned an invalid transaction anchor: {0}")]
+    InvalidAnchor(String),
+
+    #[error("Transaction not found: {0}")]
+    TransactionNotFound(String),
+
+    #[error("Transaction is pending, only its header is available: {0}")]
+    TransactionPending(String),
+
     #[error("Unknown Error.")]
     UnknownError,
 
     #[error("Error getting wallet: {0}")]
     WalletError(String),
 
-    #[error("Invalid signature")]
+    #[error("Gateway returned status {0} for the wallet balance")]
+    WalletBalanceUnavailable(u16),
+
+    #[error("Wallet balance of {balance} is below the {required} needed, including the fee")]
+    InsufficientBalance { balance: Winston, required: Winston },
+
+    #[error("Invalid wallet balance: {0}")]
+    InvalidBalance(String),
+
+    #[error(
+        "Invalid signature; signature did not verify against the transaction's owner, ensure \
+         the transaction wasn't modified after signing"
+    )]
     InvalidSignature,
 
     #[error("Error posting chunk: {0}")]
     PostChunkError(String),
 
+    #[error("Chunk rejected by gateway: {0}")]
+    ChunkRejected(String),
+
+    /// A chunk failed, so the upload stopped. `upload` lists the chunks that were uploaded and
+    /// the ones, including the failed one, that are left to resume with.
+    #[error("Upload of transaction {} stopped after a chunk failed: {source}", upload.id)]
+    #[serde(skip)]
+    UploadIncomplete {
+        upload: crate::upload::PartialUpload,
+        source: Box<Error>,
+    },
+
+    #[error("Invalid keypair: {0}")]
+    InvalidKeypair(String),
+
     #[error("Error signin: {0}")]
     SigningError(String),
+
+    #[error(
+        "No private key; this signer only holds a public key, sign the signature data elsewhere \
+         and attach it with `ArweaveSigner::apply_signature`"
+    )]
+    NoPrivateKey,
+
+    #[error(
+        "Reward of {reward} is below the required fee of {required}; get a fresh fee with \
+         `Arweave::get_fee` and recreate the transaction"
+    )]
+    FeeTooLow { reward: Winston, required: Winston },
+
+    #[error(
+        "Reward of {reward} is below the current price of {required}, so the transaction would \
+         be rejected; recreate it with a fee from `Arweave::get_fee`"
+    )]
+    InsufficientReward { reward: Winston, required: Winston },
+
+    #[error(
+        "Gateway transaction queue is full; add more gateways with `Arweave::with_gateways` to \
+         fail over to, or try again later"
+    )]
+    GatewayQueueFull,
+
+    #[error("Transaction was not visible on the gateway before the timeout")]
+    PropagationTimeout,
+
+    #[error("Request to the gateway timed out: {0}")]
+    Timeout(String),
+
+    #[error("Not enough gateways returned matching, valid transactions")]
+    NoConsensus,
+
+    #[error("ArNS name could not be resolved: {0}")]
+    NameNotFound(String),
+
+    #[error("No data found for transaction: {0}")]
+    DataNotFound(String),
+
+    #[error("Data does not match the transaction's data root")]
+    InvalidDataRoot,
+
+    #[error(
+        "Data does not match the transaction's data root {expected} and data_size; it hashes to \
+         {actual}"
+    )]
+    DataRootMismatch { expected: String, actual: String },
+
+    #[error("Transaction id {actual} is not the hash of its signature, {expected}")]
+    IdMismatch { expected: String, actual: String },
+
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+
+    #[error("Chunk offset overflow")]
+    OffsetOverflow,
+
+    #[error("Invalid gateway url: {0}")]
+    InvalidGatewayUrl(String),
+
+    #[error("Unexpected content type: {0}")]
+    UnexpectedContentType(String),
+
+    #[error(
+        "Block height is {0} seconds old, too stale to check a deadline against; check that the \
+         gateway is reachable"
+    )]
+    StaleHeight(u64),
+
+    #[error("Invalid transaction format: {0}, expected 1 or 2")]
+    InvalidTxFormat(u8),
+
+    #[error("Invalid transaction json: {0}")]
+    InvalidTxJson(String),
+
+    #[error("Gateway doesn't support range requests")]
+    RangeUnsupported,
+
+    #[error("Io error: {0}")]
+    IoError(String),
+
+    #[error("Invalid path manifest: {0}")]
+    InvalidManifest(String),
+}
+
+/// What a non-Ok status says about the request: a 4xx is the gateway rejecting the request
+/// itself, anything else points at the gateway.
+fn status_code_hint(status: u16, body: &str) -> String {
+    match status {
+        400..=499 => format!(
+            "the gateway rejected the request ({}), check the request or transaction sent",
+            body
+        ),
+        _ => format!(
+            "the gateway failed the request ({}), check its url and that it is reachable and \
+             synced",
+            body
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::{Error, Winston};
+
+    #[test]
+    fn test_display_hints() {
+        for (error, hint) in [
+            (
+                Error::StatusCodeNotOk {
+                    status: 400,
+                    body: "invalid anchor".to_string(),
+                },
+                "rejected the request (invalid anchor), check the request or transaction",
+            ),
+            (
+                Error::StatusCodeNotOk {
+                    status: 502,
+                    body: "bad gateway".to_string(),
+                },
+                "failed the request (bad gateway), check its url",
+            ),
+            (
+                Error::UnsignedTransaction,
+                "sign it with `Arweave::sign_transaction`",
+            ),
+            (Error::InvalidProof, "regenerate the chunks"),
+            (
+                Error::InvalidSignature,
+                "ensure the transaction wasn't modified after signing",
+            ),
+            (
+                Error::FeeTooLow {
+                    reward: Winston(1),
+                    required: Winston(2),
+                },
+                "get a fresh fee",
+            ),
+            (
+                Error::InsufficientReward {
+                    reward: Winston(1),
+                    required: Winston(2),
+                },
+                "would be rejected",
+            ),
+        ] {
+            let message = error.to_string();
+            assert!(message.contains(hint), "{message}");
+            assert!(!message.contains('\n'), "{message}");
+        }
+        assert!(!Error::StatusCodeNotOk {
+            status: 400,
+            body: String::new(),
+        }
+        .to_string()
+        .contains("reachable"));
+        assert_eq!(
+            Error::StatusCodeNotOk {
+                status: 503,
+                body: String::new(),
+            }
+            .to_string()
+            .split(';')
+            .next(),
+            Some("Status code 503 not Ok")
+        );
+        assert_eq!(
+            Error::InvalidSignature.to_string(),
+            "Invalid signature; signature did not verify against the transaction's owner, ensure \
+             the transaction wasn't modified after signing"
+        );
+    }
+
+    #[test]
+    fn test_source_is_kept() {
+        let json_error = serde_json::from_str::<u64>("x").unwrap_err();
+        let message = json_error.to_string();
+        let error = Error::from(json_error);
+        assert_eq!(error.to_string(), format!("Json error: {message}"));
+        assert_eq!(error.source().unwrap().to_string(), message);
+        assert!(Error::UnknownError.source().is_none());
+    }
 }
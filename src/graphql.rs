@@ -0,0 +1,140 @@
+//! Finding transactions through a gateway's GraphQL index, e.g. by tag or owner.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{crypto::base64::Base64, transaction::tags::Tag};
+
+/// Most transactions gateways return for a single query.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+pub(crate) const TRANSACTIONS_QUERY: &str = "query($tags: [TagFilter!], $owners: [String!], \
+     $first: Int, $after: String) { transactions(tags: $tags, owners: $owners, first: $first, \
+     after: $after) { pageInfo { hasNextPage } edges { cursor node { id owner { address } \
+     tags { name value } block { height } } } } }";
+
+/// Matches transactions with a tag named `name` whose value is any of `values`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagFilter {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl From<(String, Vec<String>)> for TagFilter {
+    fn from((name, values): (String, Vec<String>)) -> Self {
+        Self { name, values }
+    }
+}
+
+/// A transaction matched by a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxEdge {
+    /// Pass as `after` to get the transactions following this one.
+    pub cursor: String,
+    pub id: Base64,
+    /// Wallet address of the owner.
+    pub owner: Base64,
+    pub tags: Vec<Tag<String>>,
+    /// `None` while the transaction is pending.
+    pub block_height: Option<u64>,
+}
+
+/// One page of query results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxPage {
+    pub edges: Vec<TxEdge>,
+    pub has_next_page: bool,
+}
+
+impl TxPage {
+    /// Cursor to pass as `after` for the next page, if there is one.
+    pub fn next_cursor(&self) -> Option<&str> {
+        match self.has_next_page {
+            true => self.edges.last().map(|edge| edge.cursor.as_str()),
+            false => None,
+        }
+    }
+}
+
+/// Variables for [`TRANSACTIONS_QUERY`]. Filters that are empty are left out so they match
+/// everything, and gateways return the most recent transactions first.
+pub(crate) fn transactions_variables(
+    tags: &[TagFilter],
+    owner: Option<&Base64>,
+    first: usize,
+    after: Option<&str>,
+) -> Value {
+    json!({
+        "tags": if tags.is_empty() { Value::Null } else { json!(tags) },
+        "owners": owner.map(|owner| vec![owner.to_string()]),
+        "first": first,
+        "after": after,
+    })
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct TransactionsResponse {
+    data: TransactionsData,
+}
+
+#[derive(Deserialize, Debug)]
+struct TransactionsData {
+    transactions: Transactions,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Transactions {
+    page_info: PageInfo,
+    edges: Vec<Edge>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct Edge {
+    cursor: String,
+    node: Node,
+}
+
+#[derive(Deserialize, Debug)]
+struct Node {
+    id: Base64,
+    owner: Owner,
+    tags: Vec<Tag<String>>,
+    block: Option<Block>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Owner {
+    address: Base64,
+}
+
+#[derive(Deserialize, Debug)]
+struct Block {
+    height: u64,
+}
+
+impl From<TransactionsResponse> for TxPage {
+    fn from(res: TransactionsResponse) -> Self {
+        let transactions = res.data.transactions;
+        Self {
+            edges: transactions
+                .edges
+                .into_iter()
+                .map(|edge| TxEdge {
+                    cursor: edge.cursor,
+                    id: edge.node.id,
+                    owner: edge.node.owner.address,
+                    tags: edge.node.tags,
+                    block_height: edge.node.block.map(|block| block.height),
+                })
+                .collect(),
+            has_next_page: transactions.page_info.has_next_page,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! The HTTP layer requests to gateways go through, so that tests can answer them without a
+//! gateway.
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+
+/// Status and body of a response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+
+    /// Body as text, with invalid UTF-8 replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// Why a request got no response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpError {
+    Timeout(String),
+    Failed(String),
+}
+
+impl HttpError {
+    /// Maps to [`Error::Timeout`] for a timeout and to `other` for any other failure.
+    pub(crate) fn into_error(self, other: fn(String) -> Error) -> Error {
+        match self {
+            HttpError::Timeout(message) => Error::Timeout(message),
+            HttpError::Failed(message) => other(message),
+        }
+    }
+}
+
+impl From<reqwest::Error> for HttpError {
+    fn from(err: reqwest::Error) -> Self {
+        match err.is_timeout() {
+            true => HttpError::Timeout(err.to_string()),
+            false => HttpError::Failed(err.to_string()),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "wasm"), async_trait)]
+#[cfg_attr(feature = "wasm", async_trait(?Send))]
+pub trait HttpClient: Send + Sync {
+    async fn get(&self, url: url::Url) -> Result<HttpResponse, HttpError>;
+
+    /// Posts `body`, which is already serialized JSON.
+    async fn post_json(&self, url: url::Url, body: &[u8]) -> Result<HttpResponse, HttpError>;
+}
+
+/// Sends requests with [`reqwest`]. Used unless another client is injected.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestClient(pub reqwest::Client);
+
+impl ReqwestClient {
+    async fn response(res: reqwest::Response) -> Result<HttpResponse, HttpError> {
+        let status = res.status();
+        let body = res.bytes().await?;
+        Ok(HttpResponse::new(status, body.to_vec()))
+    }
+}
+
+#[cfg_attr(not(feature = "wasm"), async_trait)]
+#[cfg_attr(feature = "wasm", async_trait(?Send))]
+impl HttpClient for ReqwestClient {
+    async fn get(&self, url: url::Url) -> Result<HttpResponse, HttpError> {
+        Self::response(self.0.get(url).send().await?).await
+    }
+
+    async fn post_json(&self, url: url::Url, body: &[u8]) -> Result<HttpResponse, HttpError> {
+        let res = self
+            .0
+            .post(url)
+            .body(body.to_vec())
+            .header(&ACCEPT, "application/json")
+            .header(&CONTENT_TYPE, "application/json")
+            .send()
+            .await?;
+        Self::response(res).await
+    }
+}
+
+/// Client that answers requests with queued responses, in order, and records the requests it
+/// was sent. Fails requests with [`HttpError::Failed`] once the queue is empty.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Default)]
+pub struct MockHttpClient {
+    responses: std::sync::Mutex<std::collections::VecDeque<Result<HttpResponse, HttpError>>>,
+    requests: std::sync::Mutex<Vec<(&'static str, url::Url)>>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl MockHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_response(&self, response: Result<HttpResponse, HttpError>) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Method and url of each request sent, in order.
+    pub fn requests(&self) -> Vec<(&'static str, url::Url)> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn respond(&self, method: &'static str, url: url::Url) -> Result<HttpResponse, HttpError> {
+        self.requests.lock().unwrap().push((method, url));
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Err(HttpError::Failed("no response queued".to_string())))
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(not(feature = "wasm"), async_trait)]
+#[cfg_attr(feature = "wasm", async_trait(?Send))]
+impl HttpClient for MockHttpClient {
+    async fn get(&self, url: url::Url) -> Result<HttpResponse, HttpError> {
+        self.respond("GET", url)
+    }
+
+    async fn post_json(&self, url: url::Url, _body: &[u8]) -> Result<HttpResponse, HttpError> {
+        self.respond("POST", url)
+    }
+}
@@ -1,26 +1,67 @@
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    fs,
+    future::Future,
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bundle::{Bundle, DataItem};
+use clock::{Clock, SystemClock};
 
-use consts::{ARWEAVE_BASE_URL, MAX_TX_DATA};
-use crypto::base64::Base64;
+use consts::{
+    ARWEAVE_BASE_URL, BLOCK_SIZE, CHUNKS_DOWNLOAD_CONCURRENCY, CONNECT_TIMEOUT_SECS,
+    DEFAULT_CHUNKS_BUFFER, HEIGHT_CACHE_SECS, MAX_HEIGHT_AGE_SECS, MAX_TX_DATA, ORACLE_PRICE_URL,
+    REQUEST_TIMEOUT_SECS,
+};
+use crypto::{
+    base64::Base64,
+    hash::sha256,
+    merkle::{
+        chunk_ranges, generate_data_root, generate_leaves, validate_chunk, Node, Proof, HASH_SIZE,
+    },
+};
+use currency::Winston;
 use error::Error;
-use futures::{stream, Stream, StreamExt};
+use futures::{future::join_all, stream, stream::FuturesUnordered, Stream, StreamExt};
+use graphql::{
+    transactions_variables, TagFilter, TransactionsResponse, TxEdge, TxPage, MAX_PAGE_SIZE,
+    TRANSACTIONS_QUERY,
+};
+use http::{HttpClient, HttpError};
+use network::NetworkInfoClient;
 use pretend::StatusCode;
 use reqwest::Client;
+use retry::RetryPolicy;
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 use transaction::{
+    builder::TxBuilder,
     client::TxClient,
+    generator::{Generator, TxGenerator},
+    manifest::{Manifest, MANIFEST_CONTENT_TYPE},
     tags::{FromUtf8Strs, Tag},
     Tx,
 };
-use types::TxStatus;
-use upload::Uploader;
+use types::{
+    ArnsRecord, Chunk, ChunkData, ChunkUploadStatus, NetworkInfo, TransactionStatus, TxStatus,
+};
+use upload::{ChunkOrder, PartialUpload, Uploader};
 
+pub mod bundle;
 pub mod client;
+pub mod clock;
 pub mod consts;
 pub mod crypto;
 pub mod currency;
 pub mod error;
+pub mod graphql;
+pub mod http;
 pub mod network;
+pub mod retry;
 pub mod signer;
 pub mod transaction;
 pub mod types;
@@ -39,215 +80,4353 @@ pub struct OraclePricePair {
     pub usd: f32,
 }
 
+/// Cost of uploading data, from [`Arweave::estimate_upload_cost`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UploadCostEstimate {
+    pub winston: Winston,
+    pub ar: f64,
+    /// `None` when the price oracle couldn't be reached.
+    pub usd: Option<f64>,
+}
+
+/// How long [`Arweave::post_transaction`] waits for a posted transaction to show up on the
+/// gateway, and how often it checks.
+#[derive(Clone, Copy, Debug)]
+pub struct PropagationWait {
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
 pub struct Arweave {
     pub base_url: url::Url,
+    /// Gateways tried in order for reads until one succeeds, starting with `base_url`, and
+    /// checked against each other by [`Arweave::get_tx_with_consensus`].
+    pub gateways: Vec<url::Url>,
+    /// Nodes transactions and chunks are broadcast to instead of `base_url`, if any.
+    write_nodes: Vec<url::Url>,
     pub signer: ArweaveSigner,
     tx_client: TxClient,
     uploader: Uploader,
+    generator: Box<dyn Generator>,
+    propagation_wait: Option<PropagationWait>,
+    chunk_order: ChunkOrder,
+    retry_policy: RetryPolicy,
+    conservative_fees: bool,
+    oracle_url: url::Url,
+    client_options: ClientOptions,
+    /// Last fetched block height and when it was fetched.
+    height: Mutex<Option<(u64, Instant)>>,
+    clock: Arc<dyn Clock>,
+    client: Client,
+    /// Replaces `client` for the requests [`TxClient`] sends, if set.
+    http_client: Option<Arc<dyn HttpClient>>,
 }
 
 impl Default for Arweave {
     fn default() -> Self {
         let arweave_url = url::Url::from_str(ARWEAVE_BASE_URL).unwrap();
+        let client = ClientOptions::default().build();
         Self {
-            base_url: arweave_url,
+            base_url: arweave_url.clone(),
+            gateways: vec![arweave_url.clone()],
+            write_nodes: vec![],
             signer: Default::default(),
-            tx_client: TxClient::default(),
+            tx_client: TxClient::new(client.clone(), arweave_url)
+                .expect("Could not create TxClient"),
             uploader: Default::default(),
+            generator: Box::new(TxGenerator),
+            propagation_wait: None,
+            chunk_order: ChunkOrder::default(),
+            retry_policy: RetryPolicy::default(),
+            conservative_fees: false,
+            oracle_url: url::Url::from_str(ORACLE_PRICE_URL).unwrap(),
+            client_options: ClientOptions::default(),
+            height: Mutex::new(None),
+            clock: Arc::new(SystemClock),
+            client,
+            http_client: None,
+        }
+    }
+}
+
+/// Settings used when the client is rebuilt by [`Arweave::danger_accept_invalid_certs`],
+/// [`Arweave::with_compression`] and [`Arweave::with_timeout`].
+#[derive(Clone, Copy, Debug)]
+struct ClientOptions {
+    accept_invalid_certs: bool,
+    compression: bool,
+    timeout: Duration,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            accept_invalid_certs: false,
+            compression: true,
+            timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
         }
     }
 }
 
+impl ClientOptions {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn build(&self) -> Client {
+        Client::builder()
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .gzip(self.compression)
+            .brotli(self.compression)
+            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS).min(self.timeout))
+            .timeout(self.timeout)
+            .build()
+            .expect("Could not build client")
+    }
+
+    /// The browser handles certificates, compression and timeouts itself, so none of the
+    /// options apply.
+    #[cfg(target_arch = "wasm32")]
+    fn build(&self) -> Client {
+        Client::new()
+    }
+}
+
+/// Parses a gateway url as typed by a user: `arweave.net` gets `https://` prepended and
+/// `arweave://arweave.net` is read as `https://arweave.net/`, then the url is normalized as the
+/// constructors do. Returns [`Error::InvalidGatewayUrl`] if that doesn't give a usable url.
+pub fn parse_gateway_url(input: &str) -> Result<url::Url, Error> {
+    let input = input.trim();
+    let url = match input.contains("://") {
+        true => url::Url::parse(input),
+        false => url::Url::parse(&format!("https://{}", input)),
+    }
+    .map_err(|_| Error::InvalidGatewayUrl(input.to_string()))?;
+    normalize_gateway_url(url)
+}
+
+/// Checks that `url` is an absolute http(s) url with a host and makes sure its path ends with a
+/// slash, so that joining endpoint paths appends to it instead of replacing the last segment.
+/// `arweave://HOST` urls are mapped to `https://HOST/`.
+fn normalize_gateway_url(mut url: url::Url) -> Result<url::Url, Error> {
+    if url.scheme() == "arweave" {
+        // The scheme can't be changed in place between special and non-special schemes.
+        url = url::Url::parse(&format!("https://{}", &url[url::Position::BeforeHost..]))
+            .map_err(|_| Error::InvalidGatewayUrl(url.to_string()))?;
+    }
+    if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+        return Err(Error::InvalidGatewayUrl(url.to_string()));
+    }
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+/// [`normalize_gateway_url`] for builders that can't fail. Urls that can't be normalized are
+/// kept as they are, so requests to them fail instead.
+fn normalize_or_keep(url: url::Url) -> url::Url {
+    normalize_gateway_url(url.clone()).unwrap_or(url)
+}
+
 impl Arweave {
     pub fn from_keypair_path(keypair_path: PathBuf, base_url: url::Url) -> Result<Arweave, Error> {
-        let signer =
-            ArweaveSigner::from_keypair_path(keypair_path).expect("Could not create signer");
-        let tx_client = TxClient::new(reqwest::Client::new(), base_url.clone())
-            .expect("Could not create TxClient");
+        let base_url = normalize_gateway_url(base_url)?;
+        Self::from_signer(ArweaveSigner::from_keypair_path(keypair_path)?, base_url)
+    }
+
+    /// Same as [`Arweave::from_keypair_path`], but takes the JSON of the JWK instead of a path
+    /// to it, for wallets kept in an environment variable or a secret manager.
+    pub fn from_keypair_bytes(jwk: &[u8], base_url: url::Url) -> Result<Arweave, Error> {
+        let base_url = normalize_gateway_url(base_url)?;
+        Self::from_signer(ArweaveSigner::from_keypair_bytes(jwk)?, base_url)
+    }
+
+    fn from_signer(signer: ArweaveSigner, base_url: url::Url) -> Result<Arweave, Error> {
+        let client = ClientOptions::default().build();
+        let tx_client =
+            TxClient::new(client.clone(), base_url.clone()).expect("Could not create TxClient");
         let uploader = Uploader::new(base_url.clone());
         let arweave = Arweave {
+            gateways: vec![base_url.clone()],
+            write_nodes: vec![],
             base_url,
             signer,
             tx_client,
             uploader,
+            generator: Box::new(TxGenerator),
+            propagation_wait: None,
+            chunk_order: ChunkOrder::default(),
+            retry_policy: RetryPolicy::default(),
+            conservative_fees: false,
+            oracle_url: url::Url::from_str(ORACLE_PRICE_URL).unwrap(),
+            client_options: ClientOptions::default(),
+            height: Mutex::new(None),
+            clock: Arc::new(SystemClock),
+            client,
+            http_client: None,
         };
         Ok(arweave)
     }
 
+    /// Sets the gateways to read from, and to post transactions to when the primary's queue is
+    /// full. The first one becomes the primary `base_url`; reads move on to the next one when a
+    /// gateway fails, e.g. with a 5xx, a timeout or a body that doesn't parse.
+    pub fn with_gateways(mut self, gateways: Vec<url::Url>) -> Self {
+        let gateways: Vec<url::Url> = gateways.into_iter().map(normalize_or_keep).collect();
+        if let Some(primary) = gateways.first() {
+            self.base_url = primary.clone();
+            self.tx_client = self.tx_client_for(primary.clone());
+            self.uploader = self.uploader_for(primary.clone());
+        }
+        self.gateways = gateways;
+        self
+    }
+
+    /// Broadcasts transactions and their chunks to all of `write_nodes` instead of posting them
+    /// to `base_url`, which stays in use for reads, e.g. to submit straight to mining nodes.
+    /// Posting succeeds if any of the nodes accepts. An empty list posts to `base_url` again.
+    pub fn with_write_nodes(mut self, write_nodes: Vec<url::Url>) -> Self {
+        self.write_nodes = write_nodes.into_iter().map(normalize_or_keep).collect();
+        self
+    }
+
+    /// Uses `client` for all requests, so that several instances can share one connection pool.
+    /// The client's own settings, such as timeouts and proxies, then apply to every instance
+    /// sharing it.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self.tx_client = self.tx_client_for(self.base_url.clone());
+        self
+    }
+
+    /// Makes requests accept any TLS certificate, including self-signed and expired ones, so a
+    /// local gateway served over HTTPS with a self-signed certificate can be reached.
+    ///
+    /// # Security
+    ///
+    /// This turns off certificate validation entirely: anyone on the network path can
+    /// impersonate the gateway, read what is posted and feed back forged transactions and data.
+    /// Only use it for local development, never against a public gateway. Replaces any client
+    /// set with [`Arweave::with_client`].
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.client_options.accept_invalid_certs = accept_invalid_certs;
+        let client = self.client_options.build();
+        self.with_client(client)
+    }
+
+    /// Sets whether responses are requested gzip or brotli compressed (`Accept-Encoding: gzip,
+    /// br`) and decompressed transparently. On by default. Replaces any client set with
+    /// [`Arweave::with_client`].
+    ///
+    /// Decompression is safe for transport compression applied by the gateway, since the
+    /// decompressed bytes are the stored ones. It isn't when the data was uploaded already
+    /// compressed with a `Content-Encoding` tag, which gateways pass on as a response header:
+    /// the decompressed bytes then no longer hash to the transaction's data root. Turn this off
+    /// to get the raw stored bytes in that case.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.client_options.compression = compression;
+        let client = self.client_options.build();
+        self.with_client(client)
+    }
+
+    /// Sets how long a request to a gateway may take, from connecting to reading the whole
+    /// response, after which it fails with [`Error::Timeout`]. Defaults to
+    /// [`consts::REQUEST_TIMEOUT_SECS`] seconds, with [`consts::CONNECT_TIMEOUT_SECS`] seconds
+    /// to connect. Raise it for large downloads from the `/{id}` endpoint. Replaces any client
+    /// set with [`Arweave::with_client`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client_options.timeout = timeout;
+        let client = self.client_options.build();
+        self.with_client(client)
+    }
+
+    /// Sends the requests for transactions, anchors, prices, statuses, chunk downloads and
+    /// GraphQL queries through `http_client` instead of the `reqwest` client, e.g. a
+    /// `MockHttpClient`, behind the `testing` feature, to test retries deterministically. Chunk uploads and range
+    /// requests still go through the `reqwest` client.
+    pub fn with_http_client(mut self, http_client: Arc<dyn HttpClient>) -> Self {
+        self.http_client = Some(http_client);
+        self.tx_client = self.tx_client_for(self.base_url.clone());
+        self
+    }
+
+    /// Replaces the time source used for retries, timeouts and polling. Defaults to
+    /// [`SystemClock`].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.tx_client = self.tx_client.with_clock(clock.clone());
+        self.uploader = self.uploader.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Sets how posting transactions and chunks is retried. Defaults to
+    /// [`consts::CHUNKS_RETRIES`] retries a flat [`consts::CHUNKS_RETRY_SLEEP`] seconds apart.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.tx_client = self.tx_client.with_retry_policy(retry_policy);
+        self.uploader = self.uploader.with_retry_policy(retry_policy);
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Client for `url` sharing this instance's connection pool, HTTP client, clock and retry
+    /// policy.
+    fn tx_client_for(&self, url: url::Url) -> TxClient {
+        let tx_client = TxClient::new(self.client.clone(), url)
+            .expect("Could not create TxClient")
+            .with_clock(self.clock.clone())
+            .with_retry_policy(self.retry_policy);
+        match &self.http_client {
+            Some(http_client) => tx_client.with_http_client(http_client.clone()),
+            None => tx_client,
+        }
+    }
+
+    /// Runs `request` against `base_url`, then the other gateways in order, until one succeeds.
+    /// Returns the last error if they all fail.
+    async fn with_failover<T, F, Fut>(&self, request: F) -> Result<T, Error>
+    where
+        F: Fn(url::Url) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut result = request(self.base_url.clone()).await;
+        for gateway in self.gateways.iter().filter(|g| **g != self.base_url) {
+            if result.is_ok() {
+                break;
+            }
+            result = request(gateway.clone()).await;
+        }
+        result
+    }
+
+    /// Chunk uploader for `url` sharing this instance's clock and retry policy.
+    fn uploader_for(&self, url: url::Url) -> Uploader {
+        Uploader::new(url)
+            .with_clock(self.clock.clone())
+            .with_retry_policy(self.retry_policy)
+    }
+
+    /// Makes [`Arweave::post_transaction`] wait until the gateway reports the posted transaction
+    /// as pending or confirmed. Off by default.
+    pub fn with_propagation_wait(mut self, propagation_wait: PropagationWait) -> Self {
+        self.propagation_wait = Some(propagation_wait);
+        self
+    }
+
+    /// Replaces the generator [`Arweave::create_transaction`] builds transactions with, e.g. with
+    /// a `MockGenerator` in tests.
+    pub fn with_generator(mut self, generator: Box<dyn Generator>) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Sets the order chunks are posted in after the transaction header. Defaults to
+    /// [`ChunkOrder::Concurrent`].
+    pub fn with_chunk_order(mut self, chunk_order: ChunkOrder) -> Self {
+        self.chunk_order = chunk_order;
+        self
+    }
+
+    /// Makes [`Arweave::get_fee`] also price the next size bucket up and return the higher
+    /// fee, for sizes near a bucket boundary where the gateway's price can be inconsistent.
+    /// Off by default.
+    pub fn with_conservative_fees(mut self, conservative_fees: bool) -> Self {
+        self.conservative_fees = conservative_fees;
+        self
+    }
+
+    /// Sets the price oracle queried by [`Arweave::get_oracle_price`]. It must answer with the
+    /// same json as the default, [`ORACLE_PRICE_URL`].
+    pub fn with_oracle_url(mut self, oracle_url: url::Url) -> Self {
+        self.oracle_url = oracle_url;
+        self
+    }
+
+    /// Creates an unsigned transaction anchored to the gateway's current anchor. A `fee` of
+    /// zero is replaced with the fee the gateway asks for `data` and `target`. To create one
+    /// without a connection, pass the anchor to [`Arweave::create_transaction_with_anchor`] or
+    /// [`TxBuilder::last_tx`].
     pub async fn create_transaction(
         &self,
         target: Base64,
         other_tags: Vec<Tag<Base64>>,
         data: Vec<u8>,
-        quantity: u128,
-        fee: u64,
+        quantity: Winston,
+        fee: Winston,
         auto_content_tag: bool,
     ) -> Result<Tx, Error> {
-        let last_tx = self.get_last_tx().await;
-        Tx::new(
-            self.signer.get_provider(),
+        let last_tx = self.anchor().await?;
+        let fee = self.fee_or_price(fee, data.len(), &target).await?;
+        self.create_transaction_with_anchor(
             target,
+            other_tags,
             data,
             quantity,
             fee,
+            auto_content_tag,
+            last_tx,
+        )
+    }
+
+    /// Same as [`Arweave::create_transaction`], but uses `anchor` as the transaction's
+    /// `last_tx` instead of fetching one from the gateway, so no request is made. Useful for
+    /// offline signing; the anchor must still be recent when the transaction is posted.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_transaction_with_anchor(
+        &self,
+        target: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+        quantity: Winston,
+        fee: Winston,
+        auto_content_tag: bool,
+        anchor: Base64,
+    ) -> Result<Tx, Error> {
+        self.generator.new_transaction(
+            self.signer.get_provider(),
+            target,
+            data,
+            quantity.into(),
+            Self::fee_to_reward(fee)?,
+            anchor,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+
+    /// Same as [`Arweave::create_transaction`], but borrows `data` instead of taking it, and
+    /// leaves the transaction's `data` empty. Use with
+    /// [`Arweave::post_transaction_with_shared_data`] to upload large data without holding a
+    /// second copy of it.
+    pub async fn create_transaction_with_data_ref(
+        &self,
+        target: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        data: &[u8],
+        quantity: Winston,
+        fee: Winston,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        let last_tx = self.anchor().await?;
+        let fee = self.fee_or_price(fee, data.len(), &target).await?;
+        self.generator.new_transaction_with_data_ref(
+            self.signer.get_provider(),
+            target,
+            data,
+            quantity.into(),
+            Self::fee_to_reward(fee)?,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+
+    /// Same as [`Arweave::create_transaction_with_data_ref`] for the first `len` bytes of
+    /// `reader`, e.g. a file too large to load. The data root is computed reading one chunk at a
+    /// time, and the transaction's `data` is left empty. Use with
+    /// [`Arweave::post_transaction_from_reader`] to upload the data.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_transaction_from_reader<R: Read + Seek>(
+        &self,
+        target: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        reader: &mut R,
+        len: u64,
+        quantity: Winston,
+        fee: Winston,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        let last_tx = self.anchor().await?;
+        let data_size = usize::try_from(len).map_err(|_| Error::OffsetOverflow)?;
+        let fee = self.fee_or_price(fee, data_size, &target).await?;
+        Tx::new_from_reader(
+            self.signer.get_provider(),
+            target,
+            reader,
+            len,
+            quantity.into(),
+            Self::fee_to_reward(fee)?,
             last_tx,
             other_tags,
             auto_content_tag,
         )
     }
 
+    async fn fee_or_price(
+        &self,
+        fee: Winston,
+        data_size: usize,
+        target: &Base64,
+    ) -> Result<Winston, Error> {
+        if fee != Winston(0) {
+            return Ok(fee);
+        }
+        self.get_price(data_size as u64, Some(target)).await
+    }
+
+    /// Starts building a transaction with named fields, as a less error prone alternative to
+    /// [`Arweave::create_transaction`].
+    pub fn transaction_builder(&self) -> TxBuilder<'_> {
+        TxBuilder::new(self)
+    }
+
+    /// Anchor for a new transaction: the generator's fixed anchor if it has one, otherwise the
+    /// gateway's.
+    async fn anchor(&self) -> Result<Base64, Error> {
+        match self.generator.fixed_anchor() {
+            Some(anchor) => Ok(anchor),
+            None => self.get_last_tx().await,
+        }
+    }
+
+    /// Transaction rewards are a `u64` on the wire.
+    fn fee_to_reward(fee: Winston) -> Result<u64, Error> {
+        u64::try_from(fee).map_err(|_| Error::InvalidAmount(fee.to_string()))
+    }
+
+    /// Creates a transaction for a [path manifest](transaction::manifest::Manifest), checking
+    /// `manifest` against the manifest schema and tagging it with the content type gateways
+    /// look for.
+    pub async fn create_manifest_transaction(
+        &self,
+        manifest: Vec<u8>,
+        other_tags: Vec<Tag<Base64>>,
+        fee: Winston,
+    ) -> Result<Tx, Error> {
+        Manifest::from_slice(&manifest)?;
+        let mut tags = vec![Tag::from_utf8_strs("Content-Type", MANIFEST_CONTENT_TYPE)?];
+        tags.extend(other_tags);
+        self.create_data_transaction(manifest, tags, fee).await
+    }
+
+    /// Creates a transaction carrying signed `items` as an ANS-104 bundle, tagged so gateways
+    /// unpack and index the items.
+    pub async fn create_bundle_transaction(
+        &self,
+        items: Vec<DataItem>,
+        other_tags: Vec<Tag<Base64>>,
+        fee: Winston,
+    ) -> Result<Tx, Error> {
+        let bundle = Bundle::from_items(items)?;
+        let mut tags = Bundle::tags()?;
+        tags.extend(other_tags);
+        self.create_data_transaction(bundle, tags, fee).await
+    }
+
+    /// Unsigned transaction that only carries `data`, built with
+    /// [`Generator::new_data_transaction`]. The content type isn't sniffed.
+    async fn create_data_transaction(
+        &self,
+        data: Vec<u8>,
+        tags: Vec<Tag<Base64>>,
+        fee: Winston,
+    ) -> Result<Tx, Error> {
+        let last_tx = self.anchor().await?;
+        let fee = self.fee_or_price(fee, data.len(), &Base64::empty()).await?;
+        self.generator.new_data_transaction(
+            self.signer.get_provider(),
+            data,
+            Self::fee_to_reward(fee)?,
+            last_tx,
+            tags,
+            false,
+        )
+    }
+
+    /// Creates an unsigned ANS-104 data item with the generator, see
+    /// [`Generator::new_data_item`]. Sign it with [`Arweave::sign_data_item`] before adding it
+    /// to a bundle.
+    pub fn create_data_item(
+        &self,
+        target: Base64,
+        anchor: Base64,
+        tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+    ) -> Result<DataItem, Error> {
+        self.generator.new_data_item(target, anchor, tags, data)
+    }
+
+    /// Signs `item` with this client's key, which must be a 4096 bit key.
+    pub fn sign_data_item(&self, mut item: DataItem) -> Result<DataItem, Error> {
+        item.sign(self.signer.get_provider())?;
+        Ok(item)
+    }
+
     pub fn sign_transaction(&self, transaction: Tx) -> Result<Tx, Error> {
         self.signer.sign_transaction(transaction)
     }
 
-    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
-        self.signer.sign(message).0
+    /// Signs `message` with [`ArweaveSigner::sign`].
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(self.signer.sign(message)?.0)
     }
 
     pub fn verify_transaction(&self, transaction: &Tx) -> Result<(), Error> {
         ArweaveSigner::verify_transaction(transaction)
     }
 
-    pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
-        ArweaveSigner::verify(pub_key, message, signature)
+    /// The deep hash of `transaction` that gets signed, see [`Tx::signature_data`]. Computed
+    /// without signing, to compare byte for byte with another implementation.
+    pub fn transaction_deep_hash(&self, transaction: &Tx) -> Result<Vec<u8>, Error> {
+        transaction.signature_data()
     }
 
-    pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(String, u64), Error> {
-        self.tx_client
-            .post_transaction(signed_transaction)
-            .await
-            .map(|(id, reward)| (id.to_string(), reward))
+    /// Verifies the transaction's signature and id and returns the wallet address of its owner.
+    pub fn verify_and_recover(&self, transaction: &Tx) -> Result<Base64, Error> {
+        ArweaveSigner::verify_transaction(transaction)?;
+        if transaction.id.0 != sha256(&transaction.signature.0) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(Base64(sha256(&transaction.owner.0).to_vec()))
+    }
+
+    /// Verifies the signature and that the reward covers the fee the network currently asks for
+    /// a transaction of this size.
+    pub async fn verify_transaction_against_network(&self, transaction: &Tx) -> Result<(), Error> {
+        self.verify_transaction(transaction)?;
+        self.check_reward(transaction).await
     }
 
-    async fn get_last_tx(&self) -> Base64 {
-        self.tx_client.get_last_tx().await
+    /// Checks a signed transaction as the gateway would before it is posted: the signature
+    /// verifies, the id is the hash of the signature and the reward covers the current fee for
+    /// its `data_size` and `target`, failing with [`Error::InvalidSignature`],
+    /// [`Error::IdMismatch`] and [`Error::InsufficientReward`] respectively. Nothing is posted.
+    pub async fn validate_transaction(&self, transaction: &Tx) -> Result<(), Error> {
+        self.verify_transaction(transaction)?;
+        let id = Base64(sha256(&transaction.signature.0).to_vec());
+        if transaction.id != id {
+            return Err(Error::IdMismatch {
+                expected: id.to_string(),
+                actual: transaction.id.to_string(),
+            });
+        }
+        match self.check_reward(transaction).await {
+            Err(Error::FeeTooLow { reward, required }) => {
+                Err(Error::InsufficientReward { reward, required })
+            }
+            result => result,
+        }
     }
 
-    pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
-        self.tx_client.get_fee(target, data).await
+    async fn check_reward(&self, transaction: &Tx) -> Result<(), Error> {
+        let required = self
+            .tx_client
+            .get_price(transaction.data_size, Some(&transaction.target))
+            .await?;
+        if transaction.reward < required {
+            return Err(Error::FeeTooLow {
+                reward: Winston::from(transaction.reward),
+                required: Winston::from(required),
+            });
+        }
+        Ok(())
     }
 
-    pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
-        self.tx_client.get_tx(id).await
+    pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
+        ArweaveSigner::verify(pub_key, message, signature)
     }
 
-    pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
-        self.tx_client.get_tx_status(id).await
+    /// Posts `signed_transaction` to the `tx/` endpoint. Data larger than [`MAX_TX_DATA`] is
+    /// left out of the header and posted in chunks afterwards, as with [`Arweave::post_chunks`].
+    pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(String, u64), Error> {
+        self.post_transaction_with_progress(signed_transaction, |_, _| {})
+            .await
     }
 
-    pub fn get_pub_key(&self) -> String {
-        self.signer.keypair_modulus().to_string()
+    /// Same as [`Arweave::post_transaction`], calling `progress` as chunks are posted, as with
+    /// [`Arweave::post_chunks_with_progress`]. `progress` isn't called for data posted in the
+    /// header.
+    pub async fn post_transaction_with_progress(
+        &self,
+        signed_transaction: &Tx,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<(String, u64), Error> {
+        if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
+            return self
+                .post_transaction_chunks(signed_transaction, DEFAULT_CHUNKS_BUFFER, progress)
+                .await;
+        }
+        self.post_transaction_header(signed_transaction).await
     }
 
-    pub fn get_wallet_address(&self) -> String {
-        self.signer.wallet_address().to_string()
+    /// Same as [`Arweave::post_transaction`], but if the gateway rejects the transaction's
+    /// anchor as expired, anchors it to the gateway's current anchor, signs it again and posts
+    /// that instead. Returns the transaction that was accepted: when it was signed again, its
+    /// id, signature and `last_tx` differ from `signed_transaction`'s, so track it rather than
+    /// the original. A transaction that wasn't signed by this client's key, or whose key isn't
+    /// available, still fails with [`Error::AnchorExpired`].
+    pub async fn post_transaction_reanchoring(&self, signed_transaction: Tx) -> Result<Tx, Error> {
+        match self.post_transaction(&signed_transaction).await {
+            Ok(_) => Ok(signed_transaction),
+            Err(Error::AnchorExpired(reason)) => {
+                let reanchored = self.reanchor(&signed_transaction, reason).await?;
+                self.post_transaction(&reanchored).await?;
+                Ok(reanchored)
+            }
+            Err(e) => Err(e),
+        }
     }
 
-    pub async fn upload_file_from_path(
+    async fn post_transaction_header(
         &self,
-        file_path: PathBuf,
-        additional_tags: Vec<Tag<Base64>>,
-        fee: u64,
+        signed_transaction: &Tx,
     ) -> Result<(String, u64), Error> {
-        let mut auto_content_tag = true;
-        let mut additional_tags = additional_tags;
+        let (id, reward) = self.send_transaction_header(signed_transaction).await?;
+        if let Some(propagation_wait) = self.propagation_wait {
+            self.wait_for_propagation(&id, propagation_wait).await?;
+        }
+        Ok((id.to_string(), reward))
+    }
 
-        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
-            auto_content_tag = false;
-            let content_tag: Tag<Base64> =
-                Tag::from_utf8_strs("Content-Type", content_type.as_ref())?;
-            additional_tags.push(content_tag);
+    /// `signed_transaction` anchored to the gateway's current anchor and signed again, or
+    /// [`Error::AnchorExpired`] if it wasn't signed by this client's key or the key isn't
+    /// available.
+    async fn reanchor(&self, signed_transaction: &Tx, reason: String) -> Result<Tx, Error> {
+        if signed_transaction.owner != self.signer.keypair_modulus() {
+            return Err(Error::AnchorExpired(reason));
         }
+        let mut transaction = signed_transaction.clone_with_no_data()?;
+        transaction.data = signed_transaction.data.clone();
+        transaction.last_tx = self.get_last_tx().await?;
+        match self.sign_transaction(transaction) {
+            Err(Error::NoPrivateKey) => Err(Error::AnchorExpired(reason)),
+            result => result,
+        }
+    }
 
-        let data = fs::read(file_path).expect("Could not read file");
-        let transaction = self
-            .create_transaction(
-                Base64(b"".to_vec()),
-                additional_tags,
-                data,
-                0,
-                fee,
-                auto_content_tag,
-            )
-            .await
-            .expect("Could not create transaction");
-        let signed_transaction = self
-            .sign_transaction(transaction)
-            .expect("Could not sign tx");
-        let (id, reward) = if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
-            self.post_transaction_chunks(signed_transaction, 100)
-                .await
-                .expect("Could not post transaction chunks")
+    async fn send_transaction_header(
+        &self,
+        signed_transaction: &Tx,
+    ) -> Result<(Base64, u64), Error> {
+        if !self.write_nodes.is_empty() {
+            self.broadcast_transaction(signed_transaction).await
         } else {
-            self.post_transaction(&signed_transaction)
+            match self.tx_client.post_transaction(signed_transaction).await {
+                Err(Error::GatewayQueueFull) => {
+                    self.post_transaction_to_other_gateways(signed_transaction)
+                        .await
+                }
+                result => result,
+            }
+        }
+    }
+
+    /// Posts to every write node at once, returning as soon as one accepts, or the first node's
+    /// error if none does.
+    async fn broadcast_transaction(&self, signed_transaction: &Tx) -> Result<(Base64, u64), Error> {
+        first_ok(self.write_nodes.iter().map(|node| async move {
+            self.tx_client_for(node.clone())
+                .post_transaction(signed_transaction)
                 .await
-                .expect("Could not post transaction")
-        };
+        }))
+        .await
+    }
 
-        Ok((id, reward))
+    /// Posts `chunk` to `base_url`, or to every write node if there are any, succeeding if any
+    /// node accepts it.
+    async fn post_chunk(&self, chunk: Chunk, client: Client) -> Result<usize, Error> {
+        if self.write_nodes.is_empty() {
+            return self.uploader.post_chunk_with_retries(chunk, client).await;
+        }
+        first_ok(self.write_nodes.iter().map(|node| {
+            let uploader = self.uploader_for(node.clone());
+            let (chunk, client) = (chunk.clone(), client.clone());
+            async move { uploader.post_chunk_with_retries(chunk, client).await }
+        }))
+        .await
     }
 
-    async fn post_transaction_chunks(
+    /// Tries the gateways other than `base_url` in order, moving on from ones whose queue is also
+    /// full.
+    async fn post_transaction_to_other_gateways(
         &self,
-        signed_transaction: Tx,
-        chunks_buffer: usize,
-    ) -> Result<(String, u64), Error> {
-        if signed_transaction.id.0.is_empty() {
-            return Err(error::Error::UnsignedTransaction);
+        signed_transaction: &Tx,
+    ) -> Result<(Base64, u64), Error> {
+        for gateway in self.gateways.iter().filter(|g| **g != self.base_url) {
+            let tx_client = self.tx_client_for(gateway.clone());
+            match tx_client.post_transaction(signed_transaction).await {
+                Err(Error::GatewayQueueFull) => continue,
+                result => return result,
+            }
         }
+        Err(Error::GatewayQueueFull)
+    }
 
-        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
-        let (id, reward) = self.post_transaction(&transaction_with_no_data).await?;
+    /// Posts `signed_transactions`, up to `concurrency` at a time, returning a result per
+    /// transaction in the same order. Each transaction gets its own retry budget, so one that
+    /// keeps failing neither uses up the retries of the others nor fails them.
+    pub async fn post_transactions(
+        &self,
+        signed_transactions: &[Tx],
+        concurrency: usize,
+    ) -> Vec<Result<(String, u64), Error>> {
+        let mut results: Vec<(usize, _)> = stream::iter(signed_transactions.iter().enumerate())
+            .map(|(i, signed_transaction)| async move {
+                (i, self.post_transaction(signed_transaction).await)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
 
-        let results: Vec<Result<usize, Error>> =
-            Self::upload_transaction_chunks_stream(self, signed_transaction, chunks_buffer)
-                .collect()
-                .await;
+    async fn wait_for_propagation(
+        &self,
+        id: &Base64,
+        propagation_wait: PropagationWait,
+    ) -> Result<(), Error> {
+        let deadline = self.clock.now() + propagation_wait.timeout;
+        loop {
+            if self.get_tx_status(id.clone()).await.is_ok() {
+                return Ok(());
+            }
+            if self.clock.now() >= deadline {
+                return Err(Error::PropagationTimeout);
+            }
+            self.clock.sleep(propagation_wait.poll_interval).await;
+        }
+    }
 
-        results.into_iter().collect::<Result<Vec<usize>, Error>>()?;
+    /// Current block height. A height fetched less than [`HEIGHT_CACHE_SECS`] ago is reused
+    /// instead of asking the gateway again.
+    pub async fn current_height(&self) -> Result<u64, Error> {
+        if let Some((height, fetched_at)) = *self.height.lock().unwrap() {
+            if self.height_age(fetched_at) < Duration::from_secs(HEIGHT_CACHE_SECS) {
+                return Ok(height);
+            }
+        }
+        let height = self
+            .with_failover(|url| async move { self.tx_client_for(url).get_height().await })
+            .await?;
+        *self.height.lock().unwrap() = Some((height, self.clock.now()));
+        Ok(height)
+    }
 
-        Ok((id, reward))
+    /// Whether `deadline_height`, e.g. the deadline of a bundler receipt, is still ahead of the
+    /// current block. Falls back to the cached height if the gateway can't be reached, and
+    /// returns [`Error::StaleHeight`] if that is more than [`MAX_HEIGHT_AGE_SECS`] old, since
+    /// the deadline may have passed unnoticed.
+    pub async fn is_before_deadline(&self, deadline_height: u64) -> Result<bool, Error> {
+        let height = match self.current_height().await {
+            Ok(height) => height,
+            Err(err) => match *self.height.lock().unwrap() {
+                Some((height, fetched_at))
+                    if self.height_age(fetched_at) < Duration::from_secs(MAX_HEIGHT_AGE_SECS) =>
+                {
+                    height
+                }
+                Some((_, fetched_at)) => {
+                    return Err(Error::StaleHeight(self.height_age(fetched_at).as_secs()))
+                }
+                None => return Err(err),
+            },
+        };
+        Ok(height < deadline_height)
     }
 
-    fn upload_transaction_chunks_stream(
-        arweave: &Arweave,
-        signed_transaction: Tx,
-        buffer: usize,
-    ) -> impl Stream<Item = Result<usize, Error>> + '_ {
-        let client = Client::new();
-        stream::iter(0..signed_transaction.chunks.len())
-            .map(move |i| {
-                let chunk = signed_transaction.get_chunk(i).unwrap();
-                arweave
-                    .uploader
-                    .post_chunk_with_retries(chunk, client.clone())
-            })
-            .buffer_unordered(buffer)
+    fn height_age(&self, fetched_at: Instant) -> Duration {
+        self.clock.now().saturating_duration_since(fetched_at)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::{fs::File, io::Read, path::PathBuf, str::FromStr};
+    /// Fetches an anchor for a new transaction from the gateway.
+    pub async fn get_last_tx(&self) -> Result<Base64, Error> {
+        self.with_failover(|url| async move { self.tx_client_for(url).get_last_tx().await })
+            .await
+    }
 
-    use pretend::Url;
+    /// Fee for a transaction carrying `data` to `target`. With
+    /// [`Arweave::with_conservative_fees`], the price of the next [`BLOCK_SIZE`] bucket up is
+    /// requested too and the higher of the two is returned.
+    pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<Winston, Error> {
+        self.get_price(data.len() as u64, Some(&target)).await
+    }
 
-    use crate::{error::Error, transaction::Tx, Arweave, ARWEAVE_BASE_URL};
+    /// Fee for `tx` as built, from its `data_size` and `target`, to compare against its
+    /// `reward`. Follows [`Arweave::with_conservative_fees`] like [`Arweave::get_fee`].
+    pub async fn get_price_for_tx(&self, tx: &Tx) -> Result<Winston, Error> {
+        self.get_price(tx.data_size, Some(&tx.target)).await
+    }
 
-    #[test]
-    pub fn should_parse_and_verify_valid_tx() -> Result<(), Error> {
-        let mut file = File::open("res/sample_tx.json").unwrap();
-        let mut data = String::new();
-        file.read_to_string(&mut data).unwrap();
-        let tx = Tx::from_str(&data).unwrap();
+    /// Fee for uploading `bytes` of data, plus the transfer fee if there is a `target`. Follows
+    /// [`Arweave::with_conservative_fees`] like [`Arweave::get_fee`].
+    pub async fn get_price_for_bytes(
+        &self,
+        bytes: u64,
+        target: Option<Base64>,
+    ) -> Result<Winston, Error> {
+        self.get_price(bytes, target.as_ref()).await
+    }
+
+    /// The price behind [`Arweave::get_fee`], [`Arweave::get_price_for_tx`] and
+    /// [`Arweave::get_price_for_bytes`], see [`TxClient::get_price`].
+    async fn get_price(&self, data_size: u64, target: Option<&Base64>) -> Result<Winston, Error> {
+        self.with_failover(|url| async move {
+            self.get_price_from(&self.tx_client_for(url), data_size, target)
+                .await
+        })
+        .await
+        .map(Winston::from)
+    }
+
+    async fn get_price_from(
+        &self,
+        tx_client: &TxClient,
+        data_size: u64,
+        target: Option<&Base64>,
+    ) -> Result<u64, Error> {
+        if !self.conservative_fees {
+            return tx_client.get_price(data_size, target).await;
+        }
+
+        let sizes = [data_size, (data_size / BLOCK_SIZE + 1) * BLOCK_SIZE];
+        let prices = join_all(
+            sizes
+                .iter()
+                .map(|data_size| tx_client.get_price(*data_size, target)),
+        )
+        .await;
+        Ok(prices
+            .into_iter()
+            .collect::<Result<Vec<u64>, Error>>()?
+            .into_iter()
+            .max()
+            .unwrap_or_default())
+    }
+
+    /// USD price of AR from the price oracle.
+    pub async fn get_oracle_price(&self) -> Result<OraclePrice, Error> {
+        let res = self
+            .client
+            .get(self.oracle_url.clone())
+            .send()
+            .await
+            .map_err(|e| HttpError::from(e).into_error(Error::OracleUnavailable))?;
+        if res.status() != StatusCode::OK {
+            return Err(Error::OracleUnavailable(res.status().to_string()));
+        }
+        res.json::<OraclePrice>()
+            .await
+            .map_err(|e| HttpError::from(e).into_error(Error::OracleUnavailable))
+    }
+
+    /// USD price of one AR from the price oracle.
+    pub async fn get_ar_usd_price(&self) -> Result<f32, Error> {
+        Ok(self.get_oracle_price().await?.arweave.usd)
+    }
+
+    /// USD value of `fee` at the price oracle's current AR price.
+    pub async fn fee_in_usd(&self, fee: Winston) -> Result<f32, Error> {
+        let usd = self.get_ar_usd_price().await?;
+        Ok((fee.to_ar() * usd as f64) as f32)
+    }
+
+    /// Fee for uploading `data_size` bytes, in winston and AR, and in USD if the price oracle
+    /// can be reached. Only failing to get the fee itself fails the estimate.
+    pub async fn estimate_upload_cost(&self, data_size: u64) -> Result<UploadCostEstimate, Error> {
+        let (fee, oracle_price) =
+            futures::join!(self.get_price(data_size, None), self.get_oracle_price());
+        let winston = fee?;
+        let ar = winston.to_ar();
+        Ok(UploadCostEstimate {
+            winston,
+            ar,
+            usd: oracle_price.ok().map(|price| ar * price.arweave.usd as f64),
+        })
+    }
+
+    /// The transaction `id`, e.g. to inspect its tags, owner or reward, or to check with
+    /// [`Arweave::verify_transaction`] that someone else posted it. Returns
+    /// [`Error::TransactionNotFound`] if the gateway doesn't know it and
+    /// [`Error::TransactionPending`] if it isn't mined yet.
+    pub async fn get_transaction(&self, id: &Base64) -> Result<Tx, Error> {
+        self.with_failover(|url| async move { self.tx_client_for(url).get_transaction(id).await })
+            .await
+    }
+
+    /// Ids of the transactions waiting in the gateway's mempool to be mined.
+    pub async fn get_pending_transactions(&self) -> Result<Vec<Base64>, Error> {
+        self.with_failover(
+            |url| async move { self.tx_client_for(url).get_pending_transactions().await },
+        )
+        .await
+    }
+
+    /// Whether `id` is in the gateway's mempool. A posted transaction that is neither pending
+    /// nor confirmed, see [`Arweave::get_transaction_status`], was probably dropped and needs
+    /// posting again.
+    pub async fn is_pending(&self, id: &Base64) -> Result<bool, Error> {
+        Ok(self.get_pending_transactions().await?.contains(id))
+    }
+
+    pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
+        self.with_failover(|url| {
+            let id = id.clone();
+            async move { self.tx_client_for(url).get_tx(id).await }
+        })
+        .await
+    }
+
+    /// Fetches the transaction from every configured gateway and only returns it if at least
+    /// `quorum` of them served byte-identical transactions with the requested id and a valid
+    /// signature.
+    pub async fn get_tx_with_consensus(&self, id: Base64, quorum: usize) -> Result<Tx, Error> {
+        let responses = join_all(self.gateways.iter().map(|gateway| {
+            let id = id.clone();
+            async move {
+                let tx_client = self.tx_client_for(gateway.clone());
+                tx_client.get_tx(id).await
+            }
+        }))
+        .await;
+
+        let mut candidates: Vec<(Vec<u8>, Tx, usize)> = Vec::new();
+        for tx in responses.into_iter().filter_map(|res| match res {
+            Ok((_, Some(tx))) => Some(tx),
+            _ => None,
+        }) {
+            if tx.id != id || ArweaveSigner::verify_transaction(&tx).is_err() {
+                continue;
+            }
+            let bytes = serde_json::to_vec(&tx)?;
+            match candidates.iter_mut().find(|(b, _, _)| *b == bytes) {
+                Some((_, _, count)) => *count += 1,
+                None => candidates.push((bytes, tx, 1)),
+            }
+        }
+
+        candidates
+            .into_iter()
+            .find(|(_, _, count)| *count >= quorum)
+            .map(|(_, tx, _)| tx)
+            .ok_or(Error::NoConsensus)
+    }
+
+    /// Resolves an ArNS name to the id of the transaction it points at.
+    pub async fn resolve_name(&self, name: &str) -> Result<Base64, Error> {
+        let url = self
+            .base_url
+            .join(&format!("ar-io/resolver/records/{}", name))
+            .map_err(|_| Error::NameNotFound(name.to_string()))?;
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| HttpError::from(e).into_error(Error::TransactionInfoError))?;
+
+        match res.status() {
+            StatusCode::OK => res
+                .json::<ArnsRecord>()
+                .await
+                .map(|record| record.tx_id)
+                .map_err(|_| Error::NameNotFound(name.to_string())),
+            StatusCode::NOT_FOUND => Err(Error::NameNotFound(name.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    pub async fn download_data(&self, id: &Base64) -> Result<Vec<u8>, Error> {
+        let transaction = match self.get_tx(id.clone()).await? {
+            (_, Some(transaction)) => transaction,
+            (_, None) => return Err(Error::DataNotFound(id.to_string())),
+        };
+        self.download_tx_data(&transaction, CHUNKS_DOWNLOAD_CONCURRENCY)
+            .await
+    }
+
+    /// Downloads the data of transaction `id`. Chunked data is reassembled from the chunk
+    /// endpoints, anything else is read from the `/{id}` endpoint, following the gateway's
+    /// redirect if it serves the data elsewhere. With `verify`, the downloaded bytes are checked
+    /// with [`Arweave::verify_data`].
+    pub async fn get_transaction_data(&self, id: &Base64, verify: bool) -> Result<Vec<u8>, Error> {
+        match verify {
+            true => Ok(self.get_verified_data(id.clone()).await?.1),
+            false => self.download_data(id).await,
+        }
+    }
+
+    /// Downloads the data of `transaction` from the chunk endpoints, fetching up to
+    /// `concurrency` chunks at a time and checking each against the data root. Falls back to the
+    /// `/{id}` endpoint if the gateway doesn't know where the data sits in the weave.
+    async fn download_tx_data(
+        &self,
+        transaction: &Tx,
+        concurrency: usize,
+    ) -> Result<Vec<u8>, Error> {
+        self.with_failover(|url| async move {
+            Self::download_tx_data_from(&self.tx_client_for(url), transaction, concurrency).await
+        })
+        .await
+    }
+
+    async fn download_tx_data_from(
+        tx_client: &TxClient,
+        transaction: &Tx,
+        concurrency: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let tx_offset = match tx_client.get_tx_offset(&transaction.id).await? {
+            Some(tx_offset)
+                if !transaction.data_root.is_empty() && tx_offset.size == transaction.data_size =>
+            {
+                tx_offset
+            }
+            _ => return tx_client.get_data(&transaction.id).await,
+        };
+
+        let data_size = usize::try_from(tx_offset.size).map_err(|_| Error::OffsetOverflow)?;
+        let start = tx_offset
+            .offset
+            .checked_add(1)
+            .and_then(|end| end.checked_sub(tx_offset.size))
+            .ok_or(Error::OffsetOverflow)?;
+        let root_id: [u8; HASH_SIZE] = transaction
+            .data_root
+            .0
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::InvalidDataRoot)?;
+
+        let mut data = vec![0; data_size];
+        let mut chunks = stream::iter(
+            chunk_ranges(data_size)?
+                .into_iter()
+                .filter(|(min, max)| max > min),
+        )
+        .map(|(min, max)| async move {
+            let chunk = tx_client.get_chunk(start + min as u64).await?;
+            Self::validate_downloaded_chunk(root_id, min, max, &chunk)?;
+            Ok::<_, Error>((min, chunk.chunk.0))
+        })
+        .buffer_unordered(concurrency.max(1));
+
+        while let Some(res) = chunks.next().await {
+            let (min, bytes) = res?;
+            data[min..min + bytes.len()].copy_from_slice(&bytes);
+        }
+        Ok(data)
+    }
+
+    /// How many of `transaction`'s chunks the gateway has accepted. Uses the gateway's pending
+    /// chunks endpoint where there is one. Otherwise all chunks are reported as accepted if the
+    /// gateway knows where the data sits in the weave and serves valid first and last chunks,
+    /// and none are if not, so that an upload can be checked without downloading every chunk.
+    pub async fn get_chunk_upload_status(
+        &self,
+        transaction: &Tx,
+    ) -> Result<ChunkUploadStatus, Error> {
+        if let Some(status) = self
+            .tx_client
+            .get_chunk_upload_status(&transaction.id)
+            .await?
+        {
+            return Ok(status);
+        }
+
+        let data_size =
+            usize::try_from(transaction.data_size).map_err(|_| Error::OffsetOverflow)?;
+        let ranges: Vec<_> = chunk_ranges(data_size)?
+            .into_iter()
+            .filter(|(min, max)| max > min)
+            .collect();
+        let mut status = ChunkUploadStatus {
+            accepted: 0,
+            total: ranges.len() as u64,
+        };
+        let (first, last) = match (ranges.first(), ranges.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(status),
+        };
+        let root_id: [u8; HASH_SIZE] = transaction
+            .data_root
+            .0
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::InvalidDataRoot)?;
+        let start = match self.tx_client.get_tx_offset(&transaction.id).await? {
+            Some(tx_offset) if tx_offset.size == transaction.data_size => tx_offset
+                .offset
+                .checked_add(1)
+                .and_then(|end| end.checked_sub(tx_offset.size))
+                .ok_or(Error::OffsetOverflow)?,
+            _ => return Ok(status),
+        };
+
+        for (min, max) in [first, last] {
+            let served = match self.tx_client.get_chunk(start + min as u64).await {
+                Ok(chunk) => Self::validate_downloaded_chunk(root_id, min, max, &chunk).is_ok(),
+                Err(_) => false,
+            };
+            if !served {
+                return Ok(status);
+            }
+        }
+        status.accepted = status.total;
+        Ok(status)
+    }
+
+    fn validate_downloaded_chunk(
+        root_id: [u8; HASH_SIZE],
+        min_byte_range: usize,
+        max_byte_range: usize,
+        chunk: &ChunkData,
+    ) -> Result<(), Error> {
+        if chunk.chunk.0.len() != max_byte_range - min_byte_range {
+            return Err(Error::InvalidProof);
+        }
+        let node = Node {
+            id: [0; HASH_SIZE],
+            data_hash: Some(sha256(&chunk.chunk.0)),
+            min_byte_range,
+            max_byte_range,
+            left_child: None,
+            right_child: None,
+        };
+        let proof = Proof {
+            offset: max_byte_range - 1,
+            proof: chunk.data_path.0.clone(),
+        };
+        validate_chunk(root_id, node, proof)
+    }
+
+    /// Bytes `start..end` of the transaction's data, e.g. to read a file header without
+    /// downloading the whole file. `end` is exclusive, and the result is shorter than requested
+    /// if the data ends first. Returns [`Error::RangeUnsupported`] if the gateway doesn't support
+    /// range requests. The bytes can't be checked against the data root on their own.
+    pub async fn get_tx_data_range(
+        &self,
+        id: &Base64,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, Error> {
+        self.with_failover(|url| async move {
+            self.tx_client_for(url).get_data_range(id, start, end).await
+        })
+        .await
+    }
+
+    /// Id of a transaction already on chain with exactly `data`, found by querying the gateway's
+    /// GraphQL index for `data`'s data root, so that identical content needn't be uploaded
+    /// again. Needs a gateway whose index supports filtering by data root. Empty data has no
+    /// data root and is never reported as on chain.
+    pub async fn is_data_onchain(&self, data: &[u8]) -> Result<Option<Base64>, Error> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        let data_root = Base64(generate_data_root(generate_leaves(data)?)?.id.to_vec());
+        let ids = self
+            .tx_client
+            .graphql_transactions(
+                "query($dataRoot: String!) { transactions(first: 1, dataRoots: [$dataRoot]) { \
+                 edges { node { id } } } }",
+                serde_json::json!({ "dataRoot": data_root.to_string() }),
+            )
+            .await?;
+        Ok(ids.into_iter().next())
+    }
+
+    /// Up to `first` transactions matching all of the `tags` filters, each a tag name and the
+    /// values it may have, and owned by wallet address `owner` if given. With no filters, the
+    /// most recent transactions. Pages through results as needed, since gateways return at
+    /// most [`MAX_PAGE_SIZE`] per query.
+    pub async fn query_transactions(
+        &self,
+        tags: Vec<(String, Vec<String>)>,
+        owner: Option<Base64>,
+        first: usize,
+    ) -> Result<Vec<TxEdge>, Error> {
+        let tags: Vec<TagFilter> = tags.into_iter().map(TagFilter::from).collect();
+        let mut edges = Vec::new();
+        let mut after: Option<String> = None;
+        while edges.len() < first {
+            let page = self
+                .query_transactions_page(&tags, owner.as_ref(), first - edges.len(), after)
+                .await?;
+            after = page.next_cursor().map(str::to_string);
+            edges.extend(page.edges);
+            if after.is_none() {
+                break;
+            }
+        }
+        edges.truncate(first);
+        Ok(edges)
+    }
+
+    /// A single page of up to `first` transactions, capped at [`MAX_PAGE_SIZE`], following the
+    /// cursor `after` if given. Filters as [`Arweave::query_transactions`].
+    pub async fn query_transactions_page(
+        &self,
+        tags: &[TagFilter],
+        owner: Option<&Base64>,
+        first: usize,
+        after: Option<String>,
+    ) -> Result<TxPage, Error> {
+        let variables =
+            transactions_variables(tags, owner, first.min(MAX_PAGE_SIZE), after.as_deref());
+        self.with_failover(|url| {
+            let variables = variables.clone();
+            async move {
+                self.tx_client_for(url)
+                    .graphql::<TransactionsResponse>(TRANSACTIONS_QUERY, variables)
+                    .await
+            }
+        })
+        .await
+        .map(TxPage::from)
+    }
+
+    /// Checks that `data` has the `data_size` of `transaction` and hashes to its data root,
+    /// failing with [`Error::DataRootMismatch`] otherwise.
+    pub fn verify_data(transaction: &Tx, data: &[u8]) -> Result<(), Error> {
+        let actual = Self::data_root_of(data)?;
+        if transaction.data_size != data.len() as u64 || actual != transaction.data_root {
+            return Err(Error::DataRootMismatch {
+                expected: transaction.data_root.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the data attached to `tx` with [`Arweave::verify_data`]. The signature only
+    /// covers the root, so this is what catches a tampered body. Fails for a transaction whose
+    /// data was left off, e.g. one built with [`Tx::new_with_data_ref`]. Empty data matches an
+    /// empty root.
+    pub fn verify_transaction_data(&self, tx: &Tx) -> Result<(), Error> {
+        Self::verify_data(tx, &tx.data.0)
+    }
+
+    fn data_root_of(data: &[u8]) -> Result<Base64, Error> {
+        if data.is_empty() {
+            return Ok(Base64::empty());
+        }
+        let leaves = generate_leaves(data)?;
+        Ok(Base64(generate_data_root(leaves)?.id.to_vec()))
+    }
+
+    /// Resolves `name` via ArNS, then downloads the data it points at and verifies it against
+    /// the transaction's data root.
+    pub async fn get_data_by_name(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let id = self.resolve_name(name).await?;
+        let (_, data) = self.get_verified_data(id).await?;
+        Ok(data)
+    }
+
+    /// Downloads the transaction's data and parses it as JSON, provided its `Content-Type` tag
+    /// says it is JSON.
+    pub async fn get_tx_json(&self, id: Base64) -> Result<serde_json::Value, Error> {
+        let (transaction, data) = self.get_verified_data(id).await?;
+        let content_type = transaction.get_tag("Content-Type").unwrap_or_default();
+
+        let essence = content_type.split(';').next().unwrap_or("").trim();
+        if !(essence.eq_ignore_ascii_case("application/json") || essence.ends_with("+json")) {
+            return Err(Error::UnexpectedContentType(content_type));
+        }
+        serde_json::from_slice(&data).map_err(|e| Error::TransactionInfoError(e.to_string()))
+    }
+
+    async fn get_verified_data(&self, id: Base64) -> Result<(Tx, Vec<u8>), Error> {
+        let transaction = match self.get_tx(id.clone()).await? {
+            (_, Some(transaction)) => transaction,
+            (_, None) => return Err(Error::DataNotFound(id.to_string())),
+        };
+        let data = self
+            .download_tx_data(&transaction, CHUNKS_DOWNLOAD_CONCURRENCY)
+            .await?;
+        Self::verify_data(&transaction, &data)?;
+        Ok((transaction, data))
+    }
+
+    pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
+        self.with_failover(|url| {
+            let id = id.clone();
+            async move { self.tx_client_for(url).get_tx_status(id).await }
+        })
+        .await
+    }
+
+    /// Whether `id` is pending or mined, and if mined its block and number of confirmations.
+    /// Poll it to wait for enough confirmations before treating an upload as durable. Fails with
+    /// [`Error::TransactionNotFound`] if the gateway doesn't know the transaction.
+    pub async fn get_transaction_status(&self, id: &Base64) -> Result<TransactionStatus, Error> {
+        self.with_failover(
+            |url| async move { self.tx_client_for(url).get_transaction_status(id).await },
+        )
+        .await
+    }
+
+    /// The gateway's `/info`, with its `network` and `height`, e.g. to refuse posting to a
+    /// testnet or to a gateway that is still syncing.
+    pub async fn get_network_info(&self) -> Result<NetworkInfo, Error> {
+        self.with_failover(|url| async move {
+            NetworkInfoClient::with_client(url, self.client.clone())
+                .network_info()
+                .await
+        })
+        .await
+    }
+
+    /// Whether the gateway's transaction queue is longer than `threshold`, in which case posting
+    /// is likely to be slow or rejected and an uploader may want to back off or use another
+    /// gateway.
+    pub async fn gateway_is_congested(&self, threshold: u64) -> Result<bool, Error> {
+        let info = self.get_network_info().await?;
+        Ok(info.queue_length as u64 > threshold)
+    }
+
+    /// Confirmed balance of any `address`, from `wallet/{address}/balance`.
+    pub async fn get_wallet_balance(&self, address: Base64) -> Result<Winston, Error> {
+        self.with_failover(|url| self.get_wallet_balance_from(url, &address))
+            .await
+    }
+
+    async fn get_wallet_balance_from(
+        &self,
+        base_url: url::Url,
+        address: &Base64,
+    ) -> Result<Winston, Error> {
+        let url = base_url
+            .join(&format!("wallet/{}/balance", address))
+            .map_err(|e| Error::InvalidGatewayUrl(e.to_string()))?;
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| HttpError::from(e).into_error(Error::WalletError))?;
+        if res.status() != StatusCode::OK {
+            return Err(Error::WalletBalanceUnavailable(res.status().as_u16()));
+        }
+        let body = res
+            .text()
+            .await
+            .map_err(|e| HttpError::from(e).into_error(Error::WalletError))?;
+        body.trim()
+            .parse::<u128>()
+            .map(Winston)
+            .map_err(|_| Error::InvalidBalance(body))
+    }
+
+    /// Confirmed balance of the loaded wallet.
+    pub async fn get_own_balance(&self) -> Result<Winston, Error> {
+        self.get_wallet_balance(self.signer.wallet_address()).await
+    }
+
+    /// Confirmed balance of `address` less the `quantity` and `reward` of `own_pending`, the
+    /// transactions it has posted that may not be mined yet, so the result is what can still be
+    /// spent. Transactions owned by other wallets are ignored, and the result bottoms out at
+    /// zero.
+    pub async fn get_effective_balance(
+        &self,
+        address: &Base64,
+        own_pending: &[Tx],
+    ) -> Result<Winston, Error> {
+        let confirmed = self.get_wallet_balance(address.clone()).await?;
+
+        let pending = own_pending
+            .iter()
+            .filter(|tx| sha256(&tx.owner.0) == address.0[..])
+            .try_fold(Winston(0), |total, tx| {
+                total
+                    .checked_add(Winston::from(tx.quantity))
+                    .and_then(|total| total.checked_add(Winston::from(tx.reward)))
+            })
+            .ok_or_else(|| Error::WalletError("pending amounts overflow".to_string()))?;
+
+        Ok(confirmed.checked_sub(pending).unwrap_or_default())
+    }
+
+    pub fn get_pub_key(&self) -> String {
+        self.signer.keypair_modulus().to_string()
+    }
+
+    pub fn get_wallet_address(&self) -> String {
+        self.signer.wallet_address().to_string()
+    }
+
+    /// Uploads every file under `dir`, then a path manifest mapping their paths relative to
+    /// `dir` to the file transactions, with `index` as the index path. Returns the id of the
+    /// manifest transaction, through which the files can be fetched as `{id}/{path}`.
+    pub async fn upload_directory(&self, dir: &Path, index: &str) -> Result<Base64, Error> {
+        let mut paths = BTreeMap::new();
+        for relative_path in Self::files_in_dir(dir)? {
+            let data =
+                fs::read(dir.join(&relative_path)).map_err(|e| Error::IoError(e.to_string()))?;
+            let mut tags = Vec::new();
+            if let Some(content_type) = mime_guess::from_path(&relative_path).first() {
+                tags.push(Tag::from_utf8_strs("Content-Type", content_type.as_ref())?);
+            }
+            let fee = self.get_fee(Base64::empty(), data.clone()).await?;
+            let auto_content_tag = tags.is_empty();
+            let transaction = self
+                .create_transaction(
+                    Base64::empty(),
+                    tags,
+                    data,
+                    Winston(0),
+                    fee,
+                    auto_content_tag,
+                )
+                .await?;
+            let id = self.sign_and_post(transaction).await?;
+
+            let path = relative_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            paths.insert(path, id);
+        }
+
+        let manifest = Manifest::new(paths, Some(index.to_string()))?;
+        let manifest = serde_json::to_vec(&manifest)?;
+        let fee = self.get_fee(Base64::empty(), manifest.clone()).await?;
+        let transaction = self
+            .create_manifest_transaction(manifest, vec![], fee)
+            .await?;
+        self.sign_and_post(transaction).await
+    }
+
+    /// Paths of the files under `dir`, relative to it and sorted. Empty directories are skipped.
+    fn files_in_dir(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut files = Vec::new();
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(relative_dir) = dirs.pop() {
+            let entries =
+                fs::read_dir(dir.join(&relative_dir)).map_err(|e| Error::IoError(e.to_string()))?;
+            for entry in entries {
+                let entry = entry.map_err(|e| Error::IoError(e.to_string()))?;
+                let file_type = entry
+                    .file_type()
+                    .map_err(|e| Error::IoError(e.to_string()))?;
+                let relative_path = relative_dir.join(entry.file_name());
+                if file_type.is_dir() {
+                    dirs.push(relative_path);
+                } else {
+                    files.push(relative_path);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    async fn sign_and_post(&self, transaction: Tx) -> Result<Base64, Error> {
+        let signed_transaction = self.sign_transaction(transaction)?;
+        self.post_transaction(&signed_transaction).await?;
+        Ok(signed_transaction.id)
+    }
+
+    /// Sends `quantity` to `target` in a transaction without data, paying the gateway's current
+    /// transfer fee. Fails with [`Error::InsufficientBalance`], before anything is posted, if
+    /// the wallet's balance doesn't cover the quantity and the fee.
+    pub async fn transfer(
+        &self,
+        target: Base64,
+        quantity: Winston,
+    ) -> Result<(String, u64), Error> {
+        let fee = self.get_price_for_bytes(0, Some(target.clone())).await?;
+        let required = quantity
+            .checked_add(fee)
+            .ok_or_else(|| Error::InvalidAmount(quantity.to_string()))?;
+        let balance = self
+            .get_wallet_balance(self.signer.wallet_address())
+            .await?;
+        if balance < required {
+            return Err(Error::InsufficientBalance { balance, required });
+        }
+
+        let transaction = self
+            .create_transaction(target, vec![], vec![], quantity, fee, false)
+            .await?;
+        let signed_transaction = self.sign_transaction(transaction)?;
+        self.post_transaction(&signed_transaction).await
+    }
+
+    pub async fn upload_file_from_path(
+        &self,
+        file_path: PathBuf,
+        additional_tags: Vec<Tag<Base64>>,
+        fee: u64,
+    ) -> Result<(String, u64), Error> {
+        let mut auto_content_tag = true;
+        let mut additional_tags = additional_tags;
+
+        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
+            auto_content_tag = false;
+            let content_tag: Tag<Base64> =
+                Tag::from_utf8_strs("Content-Type", content_type.as_ref())?;
+            additional_tags.push(content_tag);
+        }
+
+        let data = fs::read(file_path).expect("Could not read file");
+        let transaction = self
+            .create_transaction(
+                Base64(b"".to_vec()),
+                additional_tags,
+                data,
+                Winston(0),
+                Winston::from(fee),
+                auto_content_tag,
+            )
+            .await
+            .expect("Could not create transaction");
+        let signed_transaction = self
+            .sign_transaction(transaction)
+            .expect("Could not sign tx");
+        let (id, reward) = self
+            .post_transaction(&signed_transaction)
+            .await
+            .expect("Could not post transaction");
+
+        Ok((id, reward))
+    }
+
+    async fn post_transaction_chunks(
+        &self,
+        signed_transaction: &Tx,
+        chunks_buffer: usize,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<(String, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction);
+        }
+
+        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+        let (id, reward) = self
+            .post_transaction_header(&transaction_with_no_data)
+            .await?;
+        signed_transaction.posted.set_posted();
+        self.post_chunks_with_progress(signed_transaction, chunks_buffer, progress)
+            .await?;
+
+        Ok((id, reward))
+    }
+
+    /// Posts the chunks of `signed_tx`, whose header has already been posted without data, with
+    /// up to `buffer * CHUNKS_BUFFER_FACTOR` requests in flight. Each chunk is retried following
+    /// the [`RetryPolicy`] set with [`Arweave::with_retry_policy`].
+    pub async fn post_chunks(&self, signed_tx: &Tx, buffer: usize) -> Result<(), Error> {
+        self.post_chunks_with_progress(signed_tx, buffer, |_, _| {})
+            .await
+    }
+
+    /// Same as [`Arweave::post_chunks`], calling `progress` with the number of chunks posted so
+    /// far and the total each time a chunk is accepted. Retries of a chunk aren't reported.
+    /// `progress` is called from the task awaiting the upload, one call at a time, so it
+    /// doesn't need to be `Send` or synchronized.
+    pub async fn post_chunks_with_progress(
+        &self,
+        signed_tx: &Tx,
+        buffer: usize,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), Error> {
+        if signed_tx.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction);
+        }
+
+        let total = signed_tx.chunks.len();
+        let mut uploaded = 0;
+        let mut first_error = None;
+        let mut results = Box::pin(self.upload_transaction_chunks_stream(signed_tx, buffer));
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(_) => {
+                    uploaded += 1;
+                    progress(uploaded, total);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Posts the header of `signed_transaction` and then its chunks, slicing them from `data`
+    /// rather than from the transaction. The uploader only holds a reference to `data`, so the
+    /// caller can keep sharing the same buffer. Up to `chunks_buffer * CHUNKS_BUFFER_FACTOR`
+    /// chunk requests are in flight, as for [`Arweave::post_chunks`].
+    pub async fn post_transaction_with_shared_data(
+        &self,
+        signed_transaction: &Tx,
+        data: Arc<[u8]>,
+        chunks_buffer: usize,
+    ) -> Result<(String, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction);
+        }
+        if signed_transaction.data_size != data.len() as u64 {
+            return Err(Error::InvalidDataRoot);
+        }
+
+        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+        let (id, reward) = self.post_transaction(&transaction_with_no_data).await?;
+        signed_transaction.posted.set_posted();
+
+        let results: Vec<Result<usize, Error>> = self
+            .upload_transaction_chunks_from_shared_data(signed_transaction, data, chunks_buffer)
+            .collect()
+            .await;
+
+        results.into_iter().collect::<Result<Vec<usize>, Error>>()?;
+
+        Ok((id, reward))
+    }
+
+    /// Posts a transaction built with [`Arweave::create_transaction_from_reader`], then its
+    /// chunks, read from `reader` as they are posted. At most `concurrency` chunks are posted,
+    /// and so held in memory, at once.
+    pub async fn post_transaction_from_reader<R: Read + Seek>(
+        &self,
+        signed_transaction: &Tx,
+        mut reader: R,
+        concurrency: usize,
+    ) -> Result<(String, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction);
+        }
+
+        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+        let (id, reward) = self.post_transaction(&transaction_with_no_data).await?;
+        signed_transaction.posted.set_posted();
+
+        let client = self.client.clone();
+        let results: Vec<Result<usize, Error>> = stream::iter(0..signed_transaction.chunks.len())
+            .map(|i| {
+                let chunk = signed_transaction.get_chunk_from_reader(i, &mut reader);
+                let client = client.clone();
+                async move { self.post_chunk(chunk?, client).await }
+            })
+            .buffer_unordered(self.chunk_order.concurrency(concurrency))
+            .collect()
+            .await;
+
+        results.into_iter().collect::<Result<Vec<usize>, Error>>()?;
+
+        Ok((id, reward))
+    }
+
+    /// Posts the chunks of an already posted transaction, up to `concurrency` at a time, until
+    /// `cancel` is triggered. On cancellation no new chunks are started, and chunks already in
+    /// flight get up to `grace_period` to finish. The returned [`PartialUpload`] lists the
+    /// offsets of the chunks that were and weren't uploaded. A chunk that fails stops the upload
+    /// the same way, and the [`PartialUpload`] comes back in [`Error::UploadIncomplete`] with
+    /// the chunk's error.
+    pub async fn upload_transaction_chunks_until_cancelled(
+        &self,
+        signed_transaction: &Tx,
+        concurrency: usize,
+        cancel: CancellationToken,
+        grace_period: Duration,
+    ) -> Result<PartialUpload, Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction);
+        }
+
+        let chunks = (0..signed_transaction.chunks.len())
+            .map(|i| signed_transaction.get_chunk(i))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let offsets: Vec<usize> = chunks.iter().map(|c| c.offset).collect();
+
+        let client = self.client.clone();
+        let mut chunks = chunks.into_iter();
+        let mut in_flight = FuturesUnordered::new();
+        let mut completed = Vec::new();
+        // The first chunk error; the chunk stays in `remaining`.
+        let mut failure = None;
+
+        loop {
+            let stopped = cancel.is_cancelled() || failure.is_some();
+            while !stopped && in_flight.len() < self.chunk_order.concurrency(concurrency) {
+                match chunks.next() {
+                    Some(chunk) => in_flight.push(self.post_chunk(chunk, client.clone())),
+                    None => break,
+                }
+            }
+            if in_flight.is_empty() || stopped {
+                break;
+            }
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {}
+                Some(res) = in_flight.next() => match res {
+                    Ok(offset) => completed.push(offset),
+                    Err(err) => failure = Some(err),
+                },
+            }
+        }
+
+        let drain = async {
+            while let Some(res) = in_flight.next().await {
+                match res {
+                    Ok(offset) => completed.push(offset),
+                    Err(err) => {
+                        failure.get_or_insert(err);
+                    }
+                }
+            }
+        };
+        tokio::select! {
+            biased;
+            _ = drain => {}
+            _ = self.clock.sleep(grace_period) => {}
+        }
+
+        completed.sort_unstable();
+        let remaining = offsets
+            .into_iter()
+            .filter(|o| completed.binary_search(o).is_err())
+            .collect();
+
+        let upload = PartialUpload {
+            id: signed_transaction.id.to_string(),
+            completed,
+            remaining,
+        };
+        match failure {
+            Some(err) => Err(Error::UploadIncomplete {
+                upload,
+                source: Box::new(err),
+            }),
+            None => Ok(upload),
+        }
+    }
+
+    fn upload_transaction_chunks_stream<'a>(
+        &'a self,
+        signed_transaction: &'a Tx,
+        buffer: usize,
+    ) -> impl Stream<Item = Result<usize, Error>> + 'a {
+        let client = self.client.clone();
+        stream::iter(0..signed_transaction.chunks.len())
+            .map(move |i| {
+                let chunk = signed_transaction.get_chunk(i);
+                let client = client.clone();
+                async move { self.post_chunk(chunk?, client).await }
+            })
+            .buffer_unordered(self.chunk_order.buffered_concurrency(buffer))
+    }
+
+    fn upload_transaction_chunks_from_shared_data<'a>(
+        &'a self,
+        signed_transaction: &'a Tx,
+        data: Arc<[u8]>,
+        buffer: usize,
+    ) -> impl Stream<Item = Result<usize, Error>> + 'a {
+        let client = self.client.clone();
+        stream::iter(0..signed_transaction.chunks.len())
+            .map(move |i| {
+                let chunk = signed_transaction.get_chunk_from(i, &data);
+                let client = client.clone();
+                async move { self.post_chunk(chunk?, client).await }
+            })
+            .buffer_unordered(self.chunk_order.buffered_concurrency(buffer))
+    }
+}
+
+/// Runs `futures` at once and returns the first `Ok` to complete, dropping the others, or the
+/// error of the first future in order if none succeeds.
+async fn first_ok<T, F>(futures: impl IntoIterator<Item = F>) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let mut pending: FuturesUnordered<_> = futures
+        .into_iter()
+        .enumerate()
+        .map(|(i, future)| async move { (i, future.await) })
+        .collect();
+    let mut first_error: Option<(usize, Error)> = None;
+    while let Some((i, result)) = pending.next().await {
+        match result {
+            Ok(value) => return Ok(value),
+            Err(e) if first_error.as_ref().is_none_or(|(first, _)| i < *first) => {
+                first_error = Some((i, e));
+            }
+            Err(_) => {}
+        }
+    }
+    Err(first_error.map_or(Error::UnknownError, |(_, e)| e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        fs::File,
+        io::{Read, Write},
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use flate2::{write::GzEncoder, Compression};
+    use futures::StreamExt;
+    use httpmock::{
+        prelude::HttpMockRequest,
+        Method::{GET, POST},
+        MockServer,
+    };
+    use pretend::Url;
+    use serde_json::json;
+    use tokio_test::block_on;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        bundle::{Bundle, DataItem},
+        clock::TestClock,
+        consts::{CHUNKS_RETRIES, MAX_TX_DATA, QUEUE_FULL_RETRIES},
+        crypto::{base64::Base64, merkle::MAX_CHUNK_SIZE, Provider},
+        currency::Winston,
+        error::Error,
+        parse_gateway_url,
+        retry::RetryPolicy,
+        transaction::{
+            generator::MockGenerator,
+            tags::{FromUtf8Strs, Tag},
+            Tx,
+        },
+        types::{Chunk, ChunkUploadStatus, TransactionStatus, TxStatus},
+        upload::ChunkOrder,
+        Arweave, PropagationWait, ARWEAVE_BASE_URL,
+    };
+
+    fn test_arweave(server: &MockServer) -> Arweave {
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        Arweave::from_keypair_path(path, Url::parse(&server.url("/")).unwrap()).unwrap()
+    }
+
+    fn signed_test_tx(arweave: &Arweave) -> Tx {
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            b"test".to_vec(),
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        arweave.sign_transaction(tx).unwrap()
+    }
+
+    #[test]
+    pub fn should_parse_and_verify_valid_tx() -> Result<(), Error> {
+        let mut file = File::open("res/sample_tx.json").unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let arweave =
+            Arweave::from_keypair_path(path, Url::from_str(ARWEAVE_BASE_URL).unwrap()).unwrap();
+
+        arweave.verify_transaction(&tx)?;
+
+        let mut tampered = tx;
+        tampered.signature.0[0] ^= 1;
+        assert!(matches!(
+            arweave.verify_transaction(&tampered),
+            Err(Error::InvalidSignature)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_created_transaction_carries_owner() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/price/4");
+            then.status(200).body("1234");
+        });
+        let arweave = test_arweave(&server);
+
+        let tx = block_on(arweave.create_transaction(
+            Base64::empty(),
+            vec![],
+            b"test".to_vec(),
+            Winston(0),
+            Winston(0),
+            false,
+        ))
+        .unwrap();
+
+        assert_eq!(tx.reward, 1234);
+        assert!(tx.signature.is_empty());
+        assert_eq!(tx.owner, arweave.signer.get_provider().keypair_modulus());
+        assert_eq!(tx.owner.to_string(), arweave.get_pub_key());
+    }
+
+    #[test]
+    fn test_create_transaction_anchor_errors() {
+        let server = MockServer::start();
+        let mut anchor = server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(502);
+        });
+        let arweave = test_arweave(&server);
+        let create = || {
+            block_on(arweave.create_transaction(
+                Base64::empty(),
+                vec![],
+                b"test".to_vec(),
+                Winston(0),
+                Winston(0),
+                false,
+            ))
+        };
+
+        assert!(matches!(create(), Err(Error::AnchorUnavailable(_))));
+
+        anchor.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body("not base64!");
+        });
+        assert!(matches!(create(), Err(Error::InvalidAnchor(body)) if body == "not base64!"));
+    }
+
+    #[test]
+    fn test_create_transaction_with_anchor() {
+        // Nothing is mocked, so fetching an anchor would fail.
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+
+        let tx = arweave
+            .create_transaction_with_anchor(
+                Base64::empty(),
+                vec![],
+                b"test".to_vec(),
+                Winston(0),
+                Winston(0),
+                false,
+                Base64(vec![9; 32]),
+            )
+            .unwrap();
+
+        assert_eq!(tx.last_tx, Base64(vec![9; 32]));
+    }
+
+    #[test]
+    fn test_verify_transaction_data() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let build = |data: Vec<u8>| {
+            arweave
+                .create_transaction_with_anchor(
+                    Base64::empty(),
+                    vec![],
+                    data,
+                    Winston(0),
+                    Winston(1000),
+                    false,
+                    Base64(vec![9; 32]),
+                )
+                .unwrap()
+        };
+
+        let tx = arweave.sign_transaction(build(vec![7; 300_000])).unwrap();
+        assert!(arweave.verify_transaction_data(&tx).is_ok());
+        assert!(arweave.verify_transaction_data(&build(vec![])).is_ok());
+
+        // The signature still verifies, the data doesn't.
+        let mut tampered = tx.clone_with_no_data().unwrap();
+        tampered.data = Base64(tx.data.0.clone());
+        tampered.data.0[1000] ^= 1;
+        assert!(arweave.verify_transaction(&tampered).is_ok());
+        let res = arweave.verify_transaction_data(&tampered);
+        assert!(
+            matches!(res, Err(Error::DataRootMismatch { ref expected, ref actual })
+                if *expected == tx.data_root.to_string() && expected != actual)
+        );
+
+        let header_only = tx.clone_with_no_data().unwrap();
+        assert!(matches!(
+            arweave.verify_transaction_data(&header_only),
+            Err(Error::DataRootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_post_transaction_chunks_large_data() {
+        let server = MockServer::start();
+        let header = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "data": "" }).to_string());
+            then.status(200);
+        });
+        let chunks = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let arweave = test_arweave(&server);
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            vec![3; MAX_TX_DATA as usize + 1],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let tx = arweave.sign_transaction(tx).unwrap();
+
+        let (id, _) = block_on(arweave.post_transaction(&tx)).unwrap();
+        assert_eq!(id, tx.id.to_string());
+        header.assert();
+        chunks.assert_hits(tx.chunks.len());
+    }
+
+    #[test]
+    fn test_upload_progress() {
+        static CHUNK_POSTS: Mutex<usize> = Mutex::new(0);
+        fn first_chunk_post(_: &HttpMockRequest) -> bool {
+            let mut posts = CHUNK_POSTS.lock().unwrap();
+            *posts += 1;
+            *posts == 1
+        }
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let retried = server.mock(|when, then| {
+            when.method(POST).path("/chunk").matches(first_chunk_post);
+            then.status(500);
+        });
+        let chunks = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let arweave = test_arweave(&server).with_clock(Arc::new(TestClock::new()));
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            vec![3; MAX_TX_DATA as usize + 1],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let tx = arweave.sign_transaction(tx).unwrap();
+        let total = tx.chunks.len();
+
+        let mut calls = Vec::new();
+        block_on(
+            arweave.post_transaction_with_progress(&tx, |uploaded, total| {
+                calls.push((uploaded, total))
+            }),
+        )
+        .unwrap();
+        retried.assert();
+        chunks.assert_hits(total);
+        assert_eq!(calls, (1..=total).map(|i| (i, total)).collect::<Vec<_>>());
+
+        let mut calls = Vec::new();
+        block_on(
+            arweave
+                .post_chunks_with_progress(&tx, 2, |uploaded, total| calls.push((uploaded, total))),
+        )
+        .unwrap();
+        assert_eq!(calls.last(), Some(&(total, total)));
+        assert_eq!(calls.len(), total);
+    }
+
+    #[test]
+    fn test_sequential_chunk_order() {
+        static POSTS: Mutex<Vec<(String, Option<usize>)>> = Mutex::new(Vec::new());
+        fn record_post(req: &HttpMockRequest) -> bool {
+            let offset = req
+                .body
+                .as_ref()
+                .and_then(|body| serde_json::from_slice::<Chunk>(body).ok())
+                .map(|chunk| chunk.offset);
+            POSTS.lock().unwrap().push((req.path.clone(), offset));
+            true
+        }
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).matches(record_post);
+            then.status(200).delay(Duration::from_millis(10));
+        });
+        let arweave = test_arweave(&server).with_chunk_order(ChunkOrder::Sequential);
+        let data: Vec<u8> = (0..1_000_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            data,
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let tx = arweave.sign_transaction(tx).unwrap();
+        let expected: Vec<usize> = (0..tx.chunks.len())
+            .map(|i| tx.get_chunk(i).unwrap().offset)
+            .collect();
+        assert!(expected.len() > 1);
+
+        block_on(arweave.post_transaction_chunks(&tx, 10, |_, _| {})).unwrap();
+
+        let posts = POSTS.lock().unwrap();
+        assert_eq!(posts[0].0, "/tx");
+        let offsets: Vec<usize> = posts[1..]
+            .iter()
+            .map(|(path, offset)| {
+                assert_eq!(path, "/chunk");
+                offset.unwrap()
+            })
+            .collect();
+        assert!(offsets.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(offsets, expected);
+    }
+
+    #[test]
+    fn test_shared_data_is_not_copied() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(r#"{"data": ""}"#);
+            then.status(200);
+        });
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let arweave = test_arweave(&server);
+        let data: Arc<[u8]> = (0..1_000_000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+        let tx = block_on(arweave.create_transaction_with_data_ref(
+            Base64::empty(),
+            vec![],
+            &data,
+            Winston(0),
+            Winston(1000),
+            false,
+        ))
+        .unwrap();
+        assert!(tx.data.is_empty());
+        assert_eq!(tx.data_size, data.len() as u64);
+        assert_eq!(Arc::strong_count(&data), 1);
+        let tx = arweave.sign_transaction(tx).unwrap();
+
+        let owned = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            data.to_vec(),
+            0,
+            1000,
+            tx.last_tx.clone(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        assert_eq!(tx.data_root, owned.data_root);
+        assert_eq!(
+            tx.get_chunk_from(1, &data).unwrap(),
+            owned.get_chunk(1).unwrap()
+        );
+
+        let chunks = arweave.upload_transaction_chunks_from_shared_data(&tx, data.clone(), 2);
+        assert_eq!(Arc::strong_count(&data), 2);
+        let offsets: Vec<usize> = block_on(chunks.collect::<Vec<_>>())
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(offsets.len(), tx.chunks.len());
+        assert_eq!(Arc::strong_count(&data), 1);
+        chunk_mock.assert_hits(tx.chunks.len());
+
+        block_on(arweave.post_transaction_with_shared_data(&tx, data.clone(), 2)).unwrap();
+        assert_eq!(Arc::strong_count(&data), 1);
+        tx_mock.assert();
+        chunk_mock.assert_hits(2 * tx.chunks.len());
+    }
+
+    /// Records the longest single read, to check data is read a chunk at a time.
+    struct ReadSizes<R> {
+        inner: R,
+        longest: usize,
+    }
+
+    impl<R: Read> Read for ReadSizes<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.longest = self.longest.max(n);
+            Ok(n)
+        }
+    }
+
+    impl<R: std::io::Seek> std::io::Seek for ReadSizes<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_transaction_from_reader() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        let tx_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(r#"{"data": ""}"#);
+            then.status(200);
+        });
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+        let arweave = test_arweave(&server);
+        let data: Vec<u8> = (0..16 * MAX_CHUNK_SIZE as u32 + 1000)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect();
+        let path = std::env::temp_dir().join(format!("arweave-rs-reader-{}", std::process::id()));
+        fs::write(&path, &data).unwrap();
+        let mut reader = ReadSizes {
+            inner: File::open(&path).unwrap(),
+            longest: 0,
+        };
+
+        let tx = block_on(arweave.create_transaction_from_reader(
+            Base64::empty(),
+            vec![],
+            &mut reader,
+            data.len() as u64,
+            Winston(0),
+            Winston(1000),
+            false,
+        ))
+        .unwrap();
+        assert!(tx.data.is_empty());
+        assert_eq!(tx.data_size, data.len() as u64);
+        let owned = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            data.clone(),
+            0,
+            1000,
+            tx.last_tx.clone(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        assert_eq!(tx.data_root, owned.data_root);
+        assert_eq!(tx.chunks, owned.chunks);
+
+        let tx = arweave.sign_transaction(tx).unwrap();
+        block_on(arweave.post_transaction_from_reader(&tx, &mut reader, 4)).unwrap();
+        fs::remove_file(&path).unwrap();
+        tx_mock.assert();
+        chunk_mock.assert_hits(tx.chunks.len());
+        assert!(reader.longest <= MAX_CHUNK_SIZE, "{}", reader.longest);
+        assert_eq!(
+            tx.get_chunk_from_reader(16, &mut std::io::Cursor::new(&data))
+                .unwrap(),
+            owned.get_chunk(16).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fee_above_u64_is_rejected() {
+        let arweave = test_arweave(&MockServer::start());
+        let fee = Winston(u64::MAX as u128 + 1);
+        let res = arweave.create_transaction_with_anchor(
+            Base64::empty(),
+            vec![],
+            vec![],
+            Winston(0),
+            fee,
+            false,
+            Base64(vec![9; 32]),
+        );
+        assert!(matches!(res, Err(Error::InvalidAmount(amount)) if amount == fee.to_string()));
+    }
+
+    #[test]
+    fn test_transaction_with_data_ref_uses_generator() {
+        // No anchor is mocked, so the generator's has to be used.
+        let server = MockServer::start();
+        let arweave = test_arweave(&server).with_generator(Box::new(MockGenerator {
+            anchor: Base64(vec![1; 48]),
+            id: Base64(vec![2; 32]),
+        }));
+        let tx = block_on(arweave.create_transaction_with_data_ref(
+            Base64::empty(),
+            vec![],
+            b"borrowed",
+            Winston(0),
+            Winston(1000),
+            false,
+        ))
+        .unwrap();
+        assert_eq!(tx.last_tx, Base64(vec![1; 48]));
+        assert_eq!(tx.id, Base64(vec![2; 32]));
+        assert!(tx.data.is_empty());
+    }
+
+    #[test]
+    fn test_transaction_from_reader_uses_fixed_anchor() {
+        // No anchor is mocked, so the generator's has to be used.
+        let server = MockServer::start();
+        let arweave = test_arweave(&server).with_generator(Box::new(MockGenerator {
+            anchor: Base64(vec![1; 48]),
+            id: Base64(vec![2; 32]),
+        }));
+        let data = b"from a reader".to_vec();
+        let tx = block_on(arweave.create_transaction_from_reader(
+            Base64::empty(),
+            vec![],
+            &mut std::io::Cursor::new(&data),
+            data.len() as u64,
+            Winston(0),
+            Winston(1000),
+            false,
+        ))
+        .unwrap();
+        assert_eq!(tx.last_tx, Base64(vec![1; 48]));
+    }
+
+    #[test]
+    fn test_deadline_against_fresh_and_stale_height() {
+        let server = MockServer::start();
+        let mut height_mock = server.mock(|when, then| {
+            when.method(GET).path("/height");
+            then.status(200).body("100");
+        });
+        let clock = Arc::new(TestClock::new());
+        let arweave = test_arweave(&server).with_clock(clock.clone());
+
+        assert_eq!(block_on(arweave.current_height()).unwrap(), 100);
+        assert!(block_on(arweave.is_before_deadline(101)).unwrap());
+        assert!(!block_on(arweave.is_before_deadline(100)).unwrap());
+        height_mock.assert_hits(1);
+
+        height_mock.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/height");
+            then.status(503);
+        });
+
+        clock.advance(Duration::from_secs(60));
+        assert!(block_on(arweave.is_before_deadline(101)).unwrap());
+        assert!(matches!(
+            block_on(arweave.current_height()),
+            Err(Error::NetworkInfoError(_))
+        ));
+
+        clock.advance(Duration::from_secs(120));
+        assert!(matches!(
+            block_on(arweave.is_before_deadline(101)),
+            Err(Error::StaleHeight(180))
+        ));
+    }
+
+    /// Needs a gateway served over HTTPS with a self-signed certificate, at
+    /// `ARWEAVE_SELF_SIGNED_URL` or `https://localhost:1984/`.
+    #[test]
+    #[ignore]
+    fn test_danger_accept_invalid_certs() {
+        let url = std::env::var("ARWEAVE_SELF_SIGNED_URL")
+            .unwrap_or_else(|_| "https://localhost:1984/".to_string());
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet.json").unwrap(),
+            Url::parse(&url).unwrap(),
+        )
+        .unwrap();
+        let tx_id = Base64::from_str("t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU").unwrap();
+
+        assert!(block_on(arweave.get_tx_status(tx_id.clone())).is_err());
+
+        let arweave = arweave.danger_accept_invalid_certs(true);
+        assert!(block_on(arweave.get_tx_status(tx_id)).is_ok());
+    }
+
+    #[test]
+    fn test_create_manifest_transaction() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        let arweave = test_arweave(&server);
+        let manifest = json!({
+            "manifest": "arweave/paths",
+            "version": "0.1.0",
+            "index": { "path": "index.html" },
+            "paths": { "index.html": { "id": Base64(vec![1; 32]).to_string() } },
+        });
+
+        let tx = block_on(arweave.create_manifest_transaction(
+            manifest.to_string().into_bytes(),
+            vec![],
+            Winston(1000),
+        ))
+        .unwrap();
+        let content_type =
+            Tag::<Base64>::from_utf8_strs("Content-Type", "application/x.arweave-manifest+json")
+                .unwrap();
+        assert_eq!(
+            tx.tags
+                .iter()
+                .filter(|tag| tag.name == content_type.name)
+                .collect::<Vec<_>>(),
+            vec![&content_type]
+        );
+
+        let not_a_manifest = json!({ "paths": {} });
+        assert!(matches!(
+            block_on(arweave.create_manifest_transaction(
+                not_a_manifest.to_string().into_bytes(),
+                vec![],
+                Winston(0)
+            )),
+            Err(Error::InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_bundle_transaction() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        let arweave = test_arweave(&server);
+        let crypto =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet_4096.json").unwrap())
+                .unwrap();
+        let mut item =
+            DataItem::new(Base64::empty(), Base64::empty(), vec![], b"record".to_vec()).unwrap();
+        item.sign(&crypto).unwrap();
+
+        let tx =
+            block_on(arweave.create_bundle_transaction(vec![item.clone()], vec![], Winston(1000)))
+                .unwrap();
+
+        let tags: Vec<(String, String)> = tx
+            .tags
+            .iter()
+            .map(|t| {
+                (
+                    t.name.to_utf8_string().unwrap(),
+                    t.value.to_utf8_string().unwrap(),
+                )
+            })
+            .collect();
+        for tag in [("Bundle-Format", "binary"), ("Bundle-Version", "2.0.0")] {
+            assert!(tags.contains(&(tag.0.to_string(), tag.1.to_string())));
+        }
+        assert_eq!(Bundle::from_bytes(&tx.data.0).unwrap().items, vec![item]);
+    }
+
+    #[test]
+    fn test_post_transactions_isolates_failures() {
+        let server = MockServer::start();
+        let clock = Arc::new(TestClock::new());
+        let arweave = test_arweave(&server).with_clock(clock.clone());
+        let txs: Vec<Tx> = (0..3).map(|_| signed_test_tx(&arweave)).collect();
+
+        let failing_body = json!({ "id": txs[1].id.to_string() }).to_string();
+        let failing = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(failing_body);
+            then.status(500);
+        });
+        let accepted = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+
+        let results = block_on(arweave.post_transactions(&txs, 3));
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().0, txs[0].id.to_string());
+        assert!(matches!(results[1], Err(Error::StatusCodeNotOk { .. })));
+        assert_eq!(results[2].as_ref().unwrap().0, txs[2].id.to_string());
+        failing.assert_hits(CHUNKS_RETRIES as usize + 1);
+        accepted.assert_hits(2);
+        assert_eq!(clock.sleeps().len(), CHUNKS_RETRIES as usize);
+    }
+
+    #[test]
+    fn test_expired_anchor_is_refreshed() {
+        let server = MockServer::start();
+        let fresh_anchor = Base64(vec![9; 32]);
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(fresh_anchor.to_string());
+        });
+        let accepted = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tx")
+                .json_body_partial(json!({ "last_tx": fresh_anchor.to_string() }).to_string());
+            then.status(200);
+        });
+        let expired = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(400).body("invalid_anchor");
+        });
+        let clock = Arc::new(TestClock::new());
+        let arweave = test_arweave(&server).with_clock(clock.clone());
+        let signed_tx = signed_test_tx(&arweave);
+
+        // Without opting in, the expired anchor is reported.
+        assert!(matches!(
+            block_on(arweave.post_transaction(&signed_tx)),
+            Err(Error::AnchorExpired(_))
+        ));
+        expired.assert_hits(1);
+
+        let original_id = signed_tx.id.clone();
+        let reanchored = block_on(arweave.post_transaction_reanchoring(signed_tx)).unwrap();
+        assert_ne!(reanchored.id, original_id);
+        assert_eq!(reanchored.last_tx, fresh_anchor);
+        assert!(arweave.verify_transaction(&reanchored).is_ok());
+        expired.assert_hits(2);
+        accepted.assert_hits(1);
+        assert!(clock.sleeps().is_empty());
+
+        // A transaction signed by another key can't be signed again.
+        let other = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet_4096.json").unwrap(),
+            Url::parse(&server.url("/")).unwrap(),
+        )
+        .unwrap();
+        let signed_tx = signed_test_tx(&arweave);
+        assert!(matches!(
+            block_on(other.post_transaction_reanchoring(signed_tx)),
+            Err(Error::AnchorExpired(reason)) if reason == "invalid_anchor"
+        ));
+        expired.assert_hits(3);
+    }
+
+    #[test]
+    fn test_is_pending() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx/pending");
+            then.status(200)
+                .json_body(json!([Base64(vec![1; 32]).to_string()]));
+        });
+        let arweave = test_arweave(&server);
+
+        assert!(block_on(arweave.is_pending(&Base64(vec![1; 32]))).unwrap());
+        assert!(!block_on(arweave.is_pending(&Base64(vec![2; 32]))).unwrap());
+    }
+
+    #[test]
+    fn test_transfer() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let target = Base64(vec![5; 32]);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", target));
+            then.status(200).body("1000");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!(
+                "/wallet/{}/balance",
+                arweave.signer.wallet_address()
+            ));
+            then.status(200).body("5000");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        let posted = server.mock(|when, then| {
+            when.method(POST).path("/tx").json_body_partial(
+                json!({ "target": target.to_string(), "quantity": "4000", "reward": "1000" })
+                    .to_string(),
+            );
+            then.status(200);
+        });
+
+        let (_, reward) = block_on(arweave.transfer(target.clone(), Winston(4000))).unwrap();
+        assert_eq!(reward, 1000);
+        posted.assert();
+
+        assert!(matches!(
+            block_on(arweave.transfer(target, Winston(4001))),
+            Err(Error::InsufficientBalance { balance, required })
+                if balance == Winston(5000) && required == Winston(5001)
+        ));
+        posted.assert_hits(1);
+    }
+
+    #[test]
+    fn test_post_transactions_concurrency() {
+        let server = MockServer::start();
+        let posts = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200).delay(Duration::from_millis(100));
+        });
+        let arweave = test_arweave(&server);
+        let txs: Vec<Tx> = (0..4).map(|_| signed_test_tx(&arweave)).collect();
+
+        let start = Instant::now();
+        let results = block_on(arweave.post_transactions(&txs, 2));
+
+        // Two rounds of two posts each.
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        posts.assert_hits(4);
+        let ids: Vec<String> = results.into_iter().map(|r| r.unwrap().0).collect();
+        assert_eq!(
+            ids,
+            txs.iter().map(|tx| tx.id.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_get_chunk_upload_status() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let data: Vec<u8> = (0..4 * 256 * 1024 + 100 * 1024u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            data.clone(),
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+
+        let mut pending = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/pending_chunks", signed_tx.id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(fs::read_to_string("res/pending_chunks.json").unwrap());
+        });
+        let status = block_on(arweave.get_chunk_upload_status(&signed_tx)).unwrap();
+        assert_eq!(
+            status,
+            ChunkUploadStatus {
+                accepted: 3,
+                total: 5
+            }
+        );
+        assert!(!status.is_complete());
+        pending.assert();
+        pending.delete();
+
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/pending_chunks", signed_tx.id));
+            then.status(404);
+        });
+        let mut offset = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/offset", signed_tx.id));
+            then.status(404);
+        });
+        let status = block_on(arweave.get_chunk_upload_status(&signed_tx)).unwrap();
+        assert_eq!(status.accepted, 0);
+        assert_eq!(status.total, 5);
+        offset.delete();
+
+        let start: u64 = 1_000_000_000;
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/offset", signed_tx.id));
+            then.status(200).json_body(json!({
+                "offset": (start + data.len() as u64 - 1).to_string(),
+                "size": data.len().to_string(),
+            }));
+        });
+        let chunk_mocks: Vec<_> = [0, signed_tx.chunks.len() - 1]
+            .into_iter()
+            .map(|i| {
+                let chunk = signed_tx.get_chunk(i).unwrap();
+                let min = signed_tx.chunks[i].min_byte_range as u64;
+                server.mock(|when, then| {
+                    when.method(GET).path(format!("/chunk/{}", start + min));
+                    then.status(200).json_body(json!({
+                        "chunk": chunk.chunk.to_string(),
+                        "data_path": chunk.data_path.to_string(),
+                    }));
+                })
+            })
+            .collect();
+        let status = block_on(arweave.get_chunk_upload_status(&signed_tx)).unwrap();
+        assert!(status.is_complete());
+        assert_eq!(status.total, 5);
+        chunk_mocks.iter().for_each(|m| m.assert());
+    }
 
+    #[test]
+    fn test_conservative_fee_takes_max_of_adjacent_buckets() {
+        let server = MockServer::start();
+        let exact = server.mock(|when, then| {
+            when.method(GET).path("/price/262143");
+            then.status(200).body("1000");
+        });
+        let next_bucket = server.mock(|when, then| {
+            when.method(GET).path("/price/262144");
+            then.status(200).body("1200");
+        });
+        let data = vec![0; 262_143];
+
+        let arweave = test_arweave(&server);
+        let fee = block_on(arweave.get_fee(Base64::empty(), data.clone())).unwrap();
+        assert_eq!(fee, Winston(1000));
+        exact.assert_hits(1);
+        next_bucket.assert_hits(0);
+
+        let arweave = arweave.with_conservative_fees(true);
+        let fee = block_on(arweave.get_fee(Base64::empty(), data)).unwrap();
+        assert_eq!(fee, Winston(1200));
+        exact.assert_hits(2);
+        next_bucket.assert_hits(1);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_dropping_unposted_signed_tx_warns() {
+        #[derive(Clone, Default)]
+        struct Logs(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Logs {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let arweave = test_arweave(&server);
+        let logs = Logs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let (posted_id, unposted_id) = tracing::subscriber::with_default(subscriber, || {
+            let unsigned = Tx::default();
+            drop(unsigned);
+
+            let posted = signed_test_tx(&arweave);
+            block_on(arweave.post_transaction(&posted)).unwrap();
+            let posted_id = posted.id.to_string();
+            drop(posted);
+
+            let unposted = signed_test_tx(&arweave);
+            let unposted_id = unposted.id.to_string();
+            drop(unposted);
+            (posted_id, unposted_id)
+        });
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let warnings: Vec<&str> = logs
+            .lines()
+            .filter(|line| line.contains("dropped without being posted"))
+            .collect();
+        assert_eq!(warnings.len(), 1, "{logs}");
+        assert!(warnings[0].contains("WARN"));
+        assert!(warnings[0].contains(&unposted_id));
+        assert!(!logs.contains(&posted_id));
+    }
+
+    #[test]
+    fn test_gateway_is_congested() {
+        let server = MockServer::start();
+        let info = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).json_body(json!({
+                "network": "arweave.N.1",
+                "version": 5,
+                "release": 53,
+                "height": 1_000_000,
+                "current": Base64(vec![3; 48]).to_string(),
+                "blocks": 1_000_001,
+                "peers": 40,
+                "queue_length": 500,
+                "node_state_latency": 1
+            }));
+        });
+        let arweave = test_arweave(&server);
+
+        let network_info = block_on(arweave.get_network_info()).unwrap();
+        assert_eq!(network_info.queue_length, 500);
+        assert_eq!(network_info.blocks, 1_000_001);
+        assert!(block_on(arweave.gateway_is_congested(100)).unwrap());
+        assert!(!block_on(arweave.gateway_is_congested(500)).unwrap());
+        info.assert_hits(3);
+    }
+
+    #[test]
+    fn test_get_wallet_balance() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let own_address = arweave.signer.wallet_address();
+        let other_address = Base64(vec![4; 32]);
+        let missing_address = Base64(vec![5; 32]);
+        for (address, status, body) in [
+            (&own_address, 200, "123456789012345678901\n"),
+            (&other_address, 200, "not a number"),
+            (&missing_address, 400, "Invalid address."),
+        ] {
+            server.mock(|when, then| {
+                when.method(GET)
+                    .path(format!("/wallet/{}/balance", address));
+                then.status(status).body(body);
+            });
+        }
+
+        assert_eq!(
+            block_on(arweave.get_own_balance()).unwrap(),
+            Winston(123_456_789_012_345_678_901)
+        );
+        assert!(matches!(
+            block_on(arweave.get_wallet_balance(other_address)),
+            Err(Error::InvalidBalance(body)) if body == "not a number"
+        ));
+        assert!(matches!(
+            block_on(arweave.get_wallet_balance(missing_address)),
+            Err(Error::WalletBalanceUnavailable(400))
+        ));
+    }
+
+    #[test]
+    fn test_effective_balance_subtracts_own_pending() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let address = arweave.signer.wallet_address();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200).body("10000000000000");
+        });
+        let pending_tx = |crypto: &Provider, quantity: u128, fee: u64| {
+            Tx::new(
+                crypto,
+                Base64(vec![1; 32]),
+                vec![],
+                quantity,
+                fee,
+                Base64::empty(),
+                vec![],
+                false,
+            )
+            .unwrap()
+        };
+        let other_wallet =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet_4096.json").unwrap())
+                .unwrap();
+        let own_provider = arweave.signer.get_provider();
+        let pending = vec![
+            pending_tx(own_provider, 1_500_000_000_000, 1_000),
+            pending_tx(own_provider, 250, 2_000),
+            pending_tx(&other_wallet, 5_000_000_000_000, 1_000),
+        ];
+
+        let balance = block_on(arweave.get_effective_balance(&address, &pending)).unwrap();
+        assert_eq!(
+            balance,
+            Winston(10_000_000_000_000 - 1_500_000_000_250 - 3_000)
+        );
+        assert_eq!(
+            block_on(arweave.get_effective_balance(&address, &[])).unwrap(),
+            Winston(10_000_000_000_000)
+        );
+
+        let overspent = vec![pending_tx(own_provider, 9_999_999_999_999, 1_000)];
+        assert_eq!(
+            block_on(arweave.get_effective_balance(&address, &overspent)).unwrap(),
+            Winston(0)
+        );
+    }
+
+    #[test]
+    fn test_upload_directory() {
+        static POSTED: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+        fn record_tx(req: &HttpMockRequest) -> bool {
+            if let Some(tx) = req
+                .body
+                .as_ref()
+                .and_then(|b| serde_json::from_slice(b).ok())
+            {
+                POSTED.lock().unwrap().push(tx);
+            }
+            true
+        }
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/price/");
+            then.status(200).body("1000");
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/tx").matches(record_tx);
+            then.status(200);
+        });
+        let arweave = test_arweave(&server);
+
+        let id = block_on(arweave.upload_directory(Path::new("res/site"), "index.html")).unwrap();
+
+        let posted = POSTED.lock().unwrap();
+        assert_eq!(posted.len(), 3);
+        let tx_id = |tx: &serde_json::Value| Base64::from_str(tx["id"].as_str().unwrap()).unwrap();
+        let tx_data =
+            |tx: &serde_json::Value| Base64::from_str(tx["data"].as_str().unwrap()).unwrap().0;
+        let content_type = |tx: &serde_json::Value| {
+            let tx = Tx::from_str(&tx.to_string()).unwrap();
+            let name = Base64::from_utf8_str("Content-Type").unwrap();
+            tx.tags
+                .into_iter()
+                .find(|tag| tag.name == name)
+                .map(|tag| tag.value.to_utf8_string().unwrap())
+        };
+
+        let manifest_tx = &posted[2];
+        assert_eq!(tx_id(manifest_tx), id);
+        assert_eq!(
+            content_type(manifest_tx).unwrap(),
+            "application/x.arweave-manifest+json"
+        );
+        let manifest: serde_json::Value = serde_json::from_slice(&tx_data(manifest_tx)).unwrap();
+        assert_eq!(manifest["index"]["path"], "index.html");
+        for (path, tx, file_type) in [
+            ("css/style.css", &posted[0], "text/css"),
+            ("index.html", &posted[1], "text/html"),
+        ] {
+            assert_eq!(manifest["paths"][path]["id"], tx["id"]);
+            assert_eq!(content_type(tx).unwrap(), file_type);
+            assert_eq!(
+                tx_data(tx),
+                fs::read(Path::new("res/site").join(path)).unwrap()
+            );
+        }
+        drop(posted);
+
+        let empty_dir =
+            std::env::temp_dir().join(format!("arweave-rs-empty-{}", std::process::id()));
+        fs::create_dir_all(empty_dir.join("nested")).unwrap();
+        let res = block_on(arweave.upload_directory(&empty_dir, "index.html"));
+        fs::remove_dir_all(&empty_dir).unwrap();
+        assert!(matches!(res, Err(Error::InvalidManifest(_))));
+    }
+
+    /// Needs an [arlocal](https://github.com/textury/arlocal) instance at `ARLOCAL_URL` or
+    /// `http://localhost:1984/`.
+    #[test]
+    #[ignore]
+    fn test_upload_directory_to_arlocal() {
+        let url =
+            std::env::var("ARLOCAL_URL").unwrap_or_else(|_| "http://localhost:1984/".to_string());
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet.json").unwrap(),
+            Url::parse(&url).unwrap(),
+        )
+        .unwrap();
+        let client = reqwest::Client::new();
+        let get = |path: String| block_on(client.get(arweave.base_url.join(&path).unwrap()).send());
+        get(format!(
+            "mint/{}/1000000000000",
+            arweave.get_wallet_address()
+        ))
+        .unwrap();
+
+        let id = block_on(arweave.upload_directory(Path::new("res/site"), "index.html")).unwrap();
+        get("mine".to_string()).unwrap();
+
+        let style = get(format!("{}/css/style.css", id)).unwrap();
+        assert!(style.status().is_success());
+        assert_eq!(
+            block_on(style.bytes()).unwrap().to_vec(),
+            fs::read("res/site/css/style.css").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gzipped_data_is_decompressed() {
+        let data = b"text heavy data, text heavy data, text heavy data".to_vec();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        let id = Base64(vec![5; 32]);
+
+        let server = MockServer::start();
+        let compressed = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/{}", id))
+                .header("accept-encoding", "gzip, br");
+            then.status(200)
+                .header("Content-Encoding", "gzip")
+                .body(&gzipped);
+        });
+        let raw = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", id));
+            then.status(200)
+                .header("Content-Encoding", "gzip")
+                .body(&gzipped);
+        });
+
+        let arweave = test_arweave(&server);
+        assert_eq!(block_on(arweave.tx_client.get_data(&id)).unwrap(), data);
+        compressed.assert();
+
+        let arweave = arweave.with_compression(false);
+        assert_eq!(block_on(arweave.tx_client.get_data(&id)).unwrap(), gzipped);
+        raw.assert();
+    }
+
+    #[test]
+    fn test_get_tx_data_range() {
+        let data = b"\x89PNG\r\n\x1a\n rest of a large file".to_vec();
+        let id = Base64(vec![5; 32]);
+        let unsupported_id = Base64(vec![6; 32]);
+
+        let server = MockServer::start();
+        let range = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/{}", id))
+                .header("range", "bytes=1-3");
+            then.status(206)
+                .header("Content-Range", format!("bytes 1-3/{}", data.len()))
+                .body(&data[1..4]);
+        });
+        let full = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", unsupported_id));
+            then.status(200).body(&data);
+        });
+        let arweave = test_arweave(&server);
+
+        assert_eq!(
+            block_on(arweave.get_tx_data_range(&id, 1, 4)).unwrap(),
+            b"PNG".to_vec()
+        );
+        range.assert();
+        assert!(block_on(arweave.get_tx_data_range(&id, 4, 4))
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            block_on(arweave.get_tx_data_range(&unsupported_id, 0, 8)),
+            Err(Error::RangeUnsupported)
+        ));
+        full.assert();
+    }
+
+    #[test]
+    fn test_is_data_onchain() {
+        let data: Vec<u8> = (0..300 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let tx = Tx::new(
+            &Provider::default(),
+            Base64::empty(),
+            data.clone(),
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let existing_id = Base64(vec![8; 32]);
+
+        let server = MockServer::start();
+        let found = server.mock(|when, then| {
+            when.method(POST).path("/graphql").json_body_partial(
+                json!({ "variables": { "dataRoot": tx.data_root.to_string() } }).to_string(),
+            );
+            then.status(200).json_body(json!({
+                "data": { "transactions": { "edges": [
+                    { "node": { "id": existing_id.to_string() } }
+                ] } }
+            }));
+        });
+        let not_found = server.mock(|when, then| {
+            when.method(POST).path("/graphql");
+            then.status(200)
+                .json_body(json!({ "data": { "transactions": { "edges": [] } } }));
+        });
+        let arweave = test_arweave(&server);
+
+        assert_eq!(
+            block_on(arweave.is_data_onchain(&data)).unwrap(),
+            Some(existing_id)
+        );
+        found.assert();
+        assert_eq!(
+            block_on(arweave.is_data_onchain(b"other data")).unwrap(),
+            None
+        );
+        not_found.assert();
+        assert_eq!(block_on(arweave.is_data_onchain(&[])).unwrap(), None);
+    }
+
+    #[test]
+    fn test_shared_client() {
+        let server = MockServer::start();
+        let price = server.mock(|when, then| {
+            when.method(GET)
+                .path_contains("/price/4")
+                .header("user-agent", "shared-client");
+            then.status(200).body("1000");
+        });
+        let info = server.mock(|when, then| {
+            when.method(GET)
+                .path("/info")
+                .header("user-agent", "shared-client");
+            then.status(200).json_body(json!({
+                "network": "arweave.N.1",
+                "version": 5,
+                "release": 53,
+                "height": 1,
+                "current": Base64(vec![3; 48]).to_string(),
+                "blocks": 2,
+                "peers": 1,
+                "queue_length": 0,
+                "node_state_latency": 1
+            }));
+        });
+        let client = reqwest::Client::builder()
+            .user_agent("shared-client")
+            .build()
+            .unwrap();
+        let base_url = Url::parse(&server.url("/")).unwrap();
+        let first = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet.json").unwrap(),
+            base_url.clone(),
+        )
+        .unwrap()
+        .with_client(client.clone());
+        let second = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet_4096.json").unwrap(),
+            base_url,
+        )
+        .unwrap()
+        .with_client(client);
+
+        for arweave in [&first, &second] {
+            let fee = block_on(arweave.get_fee(Base64::empty(), b"test".to_vec())).unwrap();
+            assert_eq!(fee, Winston(1000));
+        }
+        price.assert_hits(2);
+
+        block_on(first.get_network_info()).unwrap();
+        info.assert();
+    }
+
+    #[test]
+    fn test_base_url_validation() {
         let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+
         let arweave =
-            Arweave::from_keypair_path(path, Url::from_str(ARWEAVE_BASE_URL).unwrap()).unwrap();
+            Arweave::from_keypair_path(path.clone(), Url::parse(ARWEAVE_BASE_URL).unwrap())
+                .unwrap();
+        assert_eq!(arweave.base_url.as_str(), ARWEAVE_BASE_URL);
+
+        let res = Arweave::from_keypair_path(path.clone(), Url::parse("file:///tmp/").unwrap());
+        assert!(matches!(res, Err(Error::InvalidGatewayUrl(_))));
+
+        let arweave =
+            Arweave::from_keypair_path(path, Url::parse("https://example.com/gateway").unwrap())
+                .unwrap();
+        assert_eq!(arweave.base_url.as_str(), "https://example.com/gateway/");
+        assert_eq!(arweave.gateways, vec![arweave.base_url.clone()]);
+    }
+
+    #[test]
+    fn test_parse_gateway_url() {
+        for (input, anchor_url) in [
+            ("arweave.net", "https://arweave.net/tx_anchor"),
+            (" https://arweave.net ", "https://arweave.net/tx_anchor"),
+            ("arweave://arweave.net", "https://arweave.net/tx_anchor"),
+            ("arweave://arweave.net/", "https://arweave.net/tx_anchor"),
+            (
+                "example.com/gateway",
+                "https://example.com/gateway/tx_anchor",
+            ),
+            ("http://localhost:1984", "http://localhost:1984/tx_anchor"),
+            ("localhost:1984", "https://localhost:1984/tx_anchor"),
+        ] {
+            let url = parse_gateway_url(input).unwrap();
+            assert_eq!(
+                url.join("tx_anchor").unwrap().as_str(),
+                anchor_url,
+                "{}",
+                input
+            );
+        }
+        for input in ["", "https://", "ftp://example.com", "exa mple.com"] {
+            assert!(
+                matches!(parse_gateway_url(input), Err(Error::InvalidGatewayUrl(_))),
+                "{}",
+                input
+            );
+        }
+
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let arweave =
+            Arweave::from_keypair_path(path, Url::parse("arweave://arweave.net").unwrap())
+                .unwrap()
+                .with_gateways(vec![
+                    Url::parse("https://example.com/gateway").unwrap(),
+                    Url::parse("arweave://arweave.net").unwrap(),
+                ]);
+        assert_eq!(arweave.base_url.as_str(), "https://example.com/gateway/");
+        assert_eq!(arweave.gateways[1].as_str(), ARWEAVE_BASE_URL);
+    }
+
+    #[test]
+    fn test_from_keypair_bytes() {
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let jwk = fs::read(&path).unwrap();
+        let base_url = Url::parse(ARWEAVE_BASE_URL).unwrap();
+
+        let arweave = Arweave::from_keypair_bytes(&jwk, base_url.clone()).unwrap();
+        let from_path = Arweave::from_keypair_path(path, base_url.clone()).unwrap();
+        assert_eq!(arweave.get_wallet_address(), from_path.get_wallet_address());
+
+        assert!(matches!(
+            Arweave::from_keypair_bytes(b"{\"kty\":", base_url.clone()),
+            Err(Error::InvalidKeypair(_))
+        ));
+        assert!(matches!(
+            Arweave::from_keypair_path(PathBuf::from("res/missing_wallet.json"), base_url),
+            Err(Error::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_tx_json() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let payload = br#"{"name":"arweave-rs","version":1}"#.to_vec();
+        let signed_tx = |content_type: &str| {
+            let tx = Tx::new(
+                arweave.signer.get_provider(),
+                Base64::empty(),
+                payload.clone(),
+                0,
+                0,
+                Base64::empty(),
+                vec![Tag::from_utf8_strs("Content-Type", content_type).unwrap()],
+                false,
+            )
+            .unwrap();
+            arweave.sign_transaction(tx).unwrap()
+        };
+        let mock_tx = |tx: &Tx| {
+            let header = serde_json::to_string(&tx.clone_with_no_data().unwrap()).unwrap();
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}", tx.id));
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .body(header);
+            });
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/{}", tx.id));
+                then.status(200).body(&payload);
+            });
+        };
+
+        let json_tx = signed_tx("application/json; charset=utf-8");
+        mock_tx(&json_tx);
+        let value = block_on(arweave.get_tx_json(json_tx.id.clone())).unwrap();
+        assert_eq!(value["name"], "arweave-rs");
+        assert_eq!(value["version"], 1);
+
+        let text_tx = signed_tx("text/plain");
+        mock_tx(&text_tx);
+        let res = block_on(arweave.get_tx_json(text_tx.id.clone()));
+        assert!(matches!(res, Err(Error::UnexpectedContentType(t)) if t == "text/plain"));
+    }
+
+    #[test]
+    fn test_verify_and_recover() {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+        let arweave = Arweave::default();
+
+        let address = arweave.verify_and_recover(&tx).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4"
+        );
+
+        let mut tampered = tx;
+        tampered.reward += 1;
+        assert!(matches!(
+            arweave.verify_and_recover(&tampered),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_get_tx_with_consensus() {
+        let sample_tx = fs::read_to_string("res/sample_tx.json").unwrap();
+        let tampered_tx = sample_tx.replace("600912", "600913");
+        let id = Base64::from_str("t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU").unwrap();
 
-        match arweave.verify_transaction(&tx) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(Error::InvalidSignature),
+        let servers: Vec<MockServer> = (0..3).map(|_| MockServer::start()).collect();
+        for (server, body) in servers.iter().zip([&sample_tx, &sample_tx, &tampered_tx]) {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{}", id));
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .body(body);
+            });
+        }
+
+        let gateways: Vec<Url> = servers
+            .iter()
+            .map(|s| Url::parse(&s.url("/")).unwrap())
+            .collect();
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let arweave = Arweave::from_keypair_path(path, gateways[0].clone())
+            .unwrap()
+            .with_gateways(gateways);
+
+        let tx = block_on(arweave.get_tx_with_consensus(id.clone(), 2)).unwrap();
+        assert_eq!(tx, Tx::from_str(&sample_tx).unwrap());
+
+        let res = block_on(arweave.get_tx_with_consensus(id, 3));
+        assert!(matches!(res, Err(Error::NoConsensus)));
+    }
+
+    #[test]
+    fn test_reads_fail_over_to_next_gateway() {
+        let sample_tx = fs::read_to_string("res/sample_tx.json").unwrap();
+        let id = Base64::from_str("t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU").unwrap();
+
+        // The primary answers 200 with garbage and 502s, the mirror answers properly.
+        let primary = MockServer::start();
+        let garbage_tx = primary.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body("{\"id\": [oops");
+        });
+        primary.mock(|when, then| {
+            when.method(GET).path("/price/5");
+            then.status(502);
+        });
+        let mirror = MockServer::start();
+        mirror.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(&sample_tx);
+        });
+        mirror.mock(|when, then| {
+            when.method(GET).path("/price/5");
+            then.status(200).body("1234");
+        });
+
+        let gateways: Vec<Url> = [&primary, &mirror]
+            .iter()
+            .map(|s| Url::parse(&s.url("/")).unwrap())
+            .collect();
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let arweave = Arweave::from_keypair_path(path, gateways[0].clone())
+            .unwrap()
+            .with_gateways(gateways);
+
+        let (status, tx) = block_on(arweave.get_tx(id)).unwrap();
+        assert_eq!(status, super::StatusCode::OK);
+        assert_eq!(tx.unwrap(), Tx::from_str(&sample_tx).unwrap());
+        garbage_tx.assert_hits(1);
+        assert_eq!(
+            block_on(arweave.get_price_for_bytes(5, None)).unwrap(),
+            Winston(1234)
+        );
+
+        // With every gateway failing, the last error is returned.
+        let res = block_on(arweave.get_last_tx());
+        assert!(matches!(res, Err(Error::AnchorUnavailable(_))));
+    }
+
+    #[test]
+    fn test_requests_time_out() {
+        let server = MockServer::start();
+        for path in ["/tx_anchor", "/price/0", "/tx"] {
+            server.mock(|when, then| {
+                when.path(path);
+                then.status(200).delay(Duration::from_secs(2));
+            });
         }
+        let clock = Arc::new(TestClock::new());
+        let arweave = test_arweave(&server)
+            .with_timeout(Duration::from_millis(100))
+            .with_clock(clock)
+            .with_retry_policy(RetryPolicy {
+                max_retries: 1,
+                ..Default::default()
+            });
+
+        let res = block_on(arweave.get_last_tx());
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+        let res = block_on(arweave.get_price_for_bytes(0, None));
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+        let res = block_on(arweave.post_transaction(&signed_test_tx(&arweave)));
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+    }
+
+    #[test]
+    fn test_direct_requests_time_out() {
+        let server = MockServer::start();
+        server.mock(|_, then| {
+            then.status(200).delay(Duration::from_secs(2));
+        });
+        let arweave = test_arweave(&server)
+            .with_timeout(Duration::from_millis(100))
+            .with_oracle_url(Url::parse(&server.url("/oracle")).unwrap());
+
+        let res = block_on(arweave.get_oracle_price());
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+        let res = block_on(arweave.resolve_name("name"));
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+        let res = block_on(arweave.get_wallet_balance(Base64(vec![1; 32])));
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+    }
+
+    #[test]
+    fn test_post_transaction_returns_send_errors() {
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        // Nothing listens on port 1, so every attempt fails to connect.
+        let clock = Arc::new(TestClock::new());
+        let arweave = Arweave::from_keypair_path(path, Url::parse("http://127.0.0.1:1/").unwrap())
+            .unwrap()
+            .with_clock(clock.clone());
+        let signed_tx = signed_test_tx(&arweave);
+
+        let res = block_on(arweave.post_transaction(&signed_tx));
+        assert!(matches!(res, Err(Error::ArweavePostError(message)) if !message.is_empty()));
+        assert_eq!(clock.sleeps().len(), CHUNKS_RETRIES as usize);
+    }
+
+    #[test]
+    fn test_broadcast_to_write_nodes() {
+        let gateway = MockServer::start();
+        let gateway_posts = gateway.mock(|when, then| {
+            when.method(POST);
+            then.status(200);
+        });
+        let rejecting = MockServer::start();
+        rejecting.mock(|when, then| {
+            when.method(POST);
+            then.status(400).body("rejected");
+        });
+        let accepting = MockServer::start();
+        let accepted_tx = accepting.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let accepted_chunks = accepting.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&gateway)
+            .with_clock(Arc::new(TestClock::new()))
+            .with_write_nodes(vec![
+                Url::parse(&rejecting.url("/")).unwrap(),
+                Url::parse(&accepting.url("/")).unwrap(),
+            ]);
+        let signed_tx = signed_test_tx(&arweave);
+        let (id, _) = block_on(arweave.post_transaction(&signed_tx)).unwrap();
+        assert_eq!(id, signed_tx.id.to_string());
+        accepted_tx.assert();
+
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            vec![2; 600 * 1024],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let tx = arweave.sign_transaction(tx).unwrap();
+        block_on(arweave.post_chunks(&tx, 2)).unwrap();
+        accepted_chunks.assert_hits(tx.chunks.len());
+        gateway_posts.assert_hits(0);
+    }
+
+    #[test]
+    fn test_broadcast_returns_on_first_acceptance() {
+        let gateway = MockServer::start();
+        let slow = MockServer::start();
+        slow.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(500).delay(Duration::from_secs(2));
+        });
+        let accepting = MockServer::start();
+        let accepted_tx = accepting.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+
+        let arweave = test_arweave(&gateway)
+            .with_clock(Arc::new(TestClock::new()))
+            .with_write_nodes(vec![
+                Url::parse(&slow.url("/")).unwrap(),
+                Url::parse(&accepting.url("/")).unwrap(),
+            ]);
+        let signed_tx = signed_test_tx(&arweave);
+
+        let start = Instant::now();
+        let (id, _) = block_on(arweave.post_transaction(&signed_tx)).unwrap();
+        assert_eq!(id, signed_tx.id.to_string());
+        accepted_tx.assert();
+        // The slow node's retries aren't waited for.
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let server = MockServer::start();
+        let tx_post = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(500);
+        });
+        let chunk_post = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(500);
+        });
+        let clock = Arc::new(TestClock::new());
+        let policy = RetryPolicy {
+            max_retries: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(2),
+            jitter: false,
+        };
+        let expected: Vec<_> = [500, 1000, 2000, 2000].map(Duration::from_millis).to_vec();
+        // The policy carries over to clients built for other gateways.
+        let arweave = test_arweave(&server)
+            .with_retry_policy(policy)
+            .with_clock(clock.clone())
+            .with_gateways(vec![Url::parse(&server.url("/")).unwrap()]);
+        let signed_tx = signed_test_tx(&arweave);
+
+        assert!(matches!(
+            block_on(arweave.post_transaction(&signed_tx)),
+            Err(Error::StatusCodeNotOk { status: 500, .. })
+        ));
+        tx_post.assert_hits(5);
+        assert_eq!(clock.sleeps(), expected);
+
+        let chunk = signed_tx.get_chunk(0).unwrap();
+        let clock = Arc::new(TestClock::new());
+        let arweave = arweave.with_clock(clock.clone());
+        assert!(block_on(
+            arweave
+                .uploader
+                .post_chunk_with_retries(chunk, arweave.client.clone())
+        )
+        .is_err());
+        chunk_post.assert_hits(5);
+        assert_eq!(clock.sleeps(), expected);
+    }
+
+    #[test]
+    fn test_post_transaction_fails_over_when_queue_full() {
+        let busy = MockServer::start();
+        let busy_post = busy.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(503).body("Mempool is full.");
+        });
+        let accepting = MockServer::start();
+        let accepted_post = accepting.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+
+        let busy_url = Url::parse(&busy.url("/")).unwrap();
+        let clock = Arc::new(TestClock::new());
+        let arweave = test_arweave(&busy).with_clock(clock.clone());
+        let signed_tx = signed_test_tx(&arweave);
+        assert!(matches!(
+            block_on(arweave.post_transaction(&signed_tx)),
+            Err(Error::GatewayQueueFull)
+        ));
+        busy_post.assert_hits(QUEUE_FULL_RETRIES as usize + 1);
+        assert_eq!(
+            clock.sleeps(),
+            (0..QUEUE_FULL_RETRIES)
+                .map(|i| RetryPolicy::default().delay(i))
+                .collect::<Vec<_>>()
+        );
+
+        let arweave = arweave
+            .with_gateways(vec![busy_url, Url::parse(&accepting.url("/")).unwrap()])
+            .with_clock(clock);
+        let (id, _) = block_on(arweave.post_transaction(&signed_tx)).unwrap();
+        assert_eq!(id, signed_tx.id.to_string());
+        accepted_post.assert();
+    }
+
+    #[test]
+    fn test_post_transaction_waits_for_propagation() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200);
+        });
+        let arweave = test_arweave(&server).with_propagation_wait(PropagationWait {
+            timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_millis(50),
+        });
+        let signed_tx = signed_test_tx(&arweave);
+        let status_path = format!("/tx/{}/status", signed_tx.id);
+
+        let mut not_found = server.mock(|when, then| {
+            when.method(GET).path(&status_path);
+            then.status(404);
+        });
+        thread::scope(|s| {
+            s.spawn(|| {
+                while not_found.hits() == 0 {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                not_found.delete();
+                server.mock(|when, then| {
+                    when.method(GET).path(&status_path);
+                    then.status(202);
+                });
+            });
+
+            let (id, _) = block_on(arweave.post_transaction(&signed_tx)).unwrap();
+            assert_eq!(id, signed_tx.id.to_string());
+        });
+    }
+
+    #[test]
+    fn test_verify_transaction_against_network() {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+        let server = MockServer::start();
+        let mut price = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", tx.target));
+            then.status(200).body("700000");
+        });
+        let arweave = test_arweave(&server);
+
+        let res = block_on(arweave.verify_transaction_against_network(&tx));
+        assert!(matches!(
+            res,
+            Err(Error::FeeTooLow {
+                reward: Winston(600912),
+                required: Winston(700000)
+            })
+        ));
+
+        price.delete();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", tx.target));
+            then.status(200).body("600912");
+        });
+        assert!(block_on(arweave.verify_transaction_against_network(&tx)).is_ok());
+    }
+
+    #[test]
+    fn test_transaction_deep_hash() {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+        let arweave = test_arweave(&MockServer::start());
+        assert_eq!(
+            Base64(arweave.transaction_deep_hash(&tx).unwrap()).to_string(),
+            "Sg9K__jNL-Vrw0VM1_kiusUfsqNINk6zE7IBhLfng9WSywZjaufXx7WrNP_NN8t1"
+        );
+    }
+
+    #[test]
+    fn test_validate_transaction() {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+        let server = MockServer::start();
+        let mut price = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", tx.target));
+            then.status(200).body("600912");
+        });
+        let arweave = test_arweave(&server);
+        assert!(block_on(arweave.validate_transaction(&tx)).is_ok());
+        price.assert();
+
+        let mut wrong_id =
+            Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+        wrong_id.id = Base64(vec![1; 32]);
+        assert!(matches!(
+            block_on(arweave.validate_transaction(&wrong_id)),
+            Err(Error::IdMismatch { expected, actual })
+                if expected == tx.id.to_string() && actual == wrong_id.id.to_string()
+        ));
+
+        let mut tampered =
+            Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+        tampered.reward += 1;
+        assert!(matches!(
+            block_on(arweave.validate_transaction(&tampered)),
+            Err(Error::InvalidSignature)
+        ));
+        // Neither failure needs the fee.
+        price.assert_hits(1);
+
+        price.delete();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/price/0/{}", tx.target));
+            then.status(200).body("700000");
+        });
+        assert!(matches!(
+            block_on(arweave.validate_transaction(&tx)),
+            Err(Error::InsufficientReward {
+                reward: Winston(600912),
+                required: Winston(700000)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_estimate_upload_cost_without_oracle() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/price/1000");
+            then.status(200).body("2000000000000");
+        });
+        let mut oracle = server.mock(|when, then| {
+            when.method(GET).path("/oracle");
+            then.status(503);
+        });
+        let arweave =
+            test_arweave(&server).with_oracle_url(Url::parse(&server.url("/oracle")).unwrap());
+
+        assert!(matches!(
+            block_on(arweave.get_oracle_price()),
+            Err(Error::OracleUnavailable(_))
+        ));
+        let estimate = block_on(arweave.estimate_upload_cost(1000)).unwrap();
+        assert_eq!(estimate.winston, Winston(2_000_000_000_000));
+        assert_eq!(estimate.ar, 2.0);
+        assert_eq!(estimate.usd, None);
+
+        oracle.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/oracle");
+            then.status(200)
+                .json_body(json!({ "arweave": { "usd": 7.5 } }));
+        });
+        let estimate = block_on(arweave.estimate_upload_cost(1000)).unwrap();
+        assert_eq!(estimate.usd, Some(15.0));
+    }
+
+    #[test]
+    fn test_fee_in_usd() {
+        let server = MockServer::start();
+        let mut oracle = server.mock(|when, then| {
+            when.method(GET).path("/oracle");
+            then.status(200)
+                .json_body(json!({ "arweave": { "usd": 7.5 } }));
+        });
+        let arweave =
+            test_arweave(&server).with_oracle_url(Url::parse(&server.url("/oracle")).unwrap());
+
+        assert_eq!(block_on(arweave.get_ar_usd_price()).unwrap(), 7.5);
+        assert_eq!(
+            block_on(arweave.fee_in_usd(Winston(500_000_000_000))).unwrap(),
+            3.75
+        );
+
+        oracle.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/oracle");
+            then.status(200).json_body(json!({ "arweave": {} }));
+        });
+        assert!(matches!(
+            block_on(arweave.fee_in_usd(Winston(500_000_000_000))),
+            Err(Error::OracleUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_transaction_status() {
+        let server = MockServer::start();
+        let confirmed = Base64(vec![1; 32]);
+        let pending = Base64(vec![2; 32]);
+        let missing = Base64(vec![3; 32]);
+        let block_indep_hash = Base64(vec![4; 48]);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", confirmed));
+            then.status(200).json_body(json!({
+                "block_height": 1_000_000,
+                "block_indep_hash": block_indep_hash.to_string(),
+                "number_of_confirmations": 12
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", pending));
+            then.status(202).body("Pending");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}/status", missing));
+            then.status(404).body("Not Found");
+        });
+        let arweave = test_arweave(&server);
+
+        assert_eq!(
+            block_on(arweave.get_transaction_status(&confirmed)).unwrap(),
+            TransactionStatus::Confirmed(TxStatus {
+                block_height: 1_000_000,
+                block_indep_hash,
+                number_of_confirmations: 12
+            })
+        );
+        assert_eq!(
+            block_on(arweave.get_transaction_status(&pending)).unwrap(),
+            TransactionStatus::Pending
+        );
+        assert!(matches!(
+            block_on(arweave.get_transaction_status(&missing)),
+            Err(Error::TransactionNotFound(id)) if id == missing.to_string()
+        ));
+    }
+
+    #[test]
+    fn test_get_price_for_bytes() {
+        let server = MockServer::start();
+        let target = Base64(vec![5; 32]);
+        server.mock(|when, then| {
+            when.method(GET).path("/price/1000");
+            then.status(200).body("1000");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/price/2000");
+            then.status(200).body("2000");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/price/1000/{}", target));
+            then.status(200).body("1500");
+        });
+        let arweave = test_arweave(&server);
+
+        let small = block_on(arweave.get_price_for_bytes(1000, None)).unwrap();
+        let large = block_on(arweave.get_price_for_bytes(2000, None)).unwrap();
+        assert_eq!((small, large), (Winston(1000), Winston(2000)));
+        assert_eq!(
+            block_on(arweave.get_price_for_bytes(1000, Some(target))).unwrap(),
+            Winston(1500)
+        );
+        assert!(matches!(
+            block_on(arweave.get_price_for_bytes(3000, None)),
+            Err(Error::GetPriceError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_price_for_tx() {
+        let tx = Tx::new(
+            &Provider::default(),
+            Base64(vec![5; 32]),
+            vec![1; 1000],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let server = MockServer::start();
+        let price = server.mock(|when, then| {
+            when.method(GET).path(format!("/price/1000/{}", tx.target));
+            then.status(200).body("123456");
+        });
+        let arweave = test_arweave(&server);
+
+        assert_eq!(
+            block_on(arweave.get_price_for_tx(&tx)).unwrap(),
+            Winston(123456)
+        );
+        price.assert();
+    }
+
+    #[test]
+    fn test_cancelled_upload_drains_in_flight_chunks() {
+        let server = MockServer::start();
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200).delay(Duration::from_millis(500));
+        });
+        let arweave = test_arweave(&server);
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            vec![7; 5 * 256 * 1024],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let offsets: Vec<usize> = (0..signed_tx.chunks.len())
+            .map(|i| signed_tx.get_chunk(i).unwrap().offset)
+            .collect();
+        assert_eq!(offsets.len(), 5);
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            trigger.cancel();
+        });
+
+        let summary = block_on(arweave.upload_transaction_chunks_until_cancelled(
+            &signed_tx,
+            2,
+            cancel,
+            Duration::from_secs(5),
+        ))
+        .unwrap();
+
+        chunk_mock.assert_hits(2);
+        assert_eq!(summary.id, signed_tx.id.to_string());
+        assert_eq!(summary.completed, offsets[..2]);
+        assert_eq!(summary.remaining, offsets[2..]);
+        assert!(!summary.is_complete());
+    }
+
+    #[test]
+    fn test_failed_chunk_keeps_partial_upload() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            vec![7; 5 * 256 * 1024],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let offsets: Vec<usize> = (0..signed_tx.chunks.len())
+            .map(|i| signed_tx.get_chunk(i).unwrap().offset)
+            .collect();
+        let rejected = server.mock(|when, then| {
+            when.method(POST)
+                .path("/chunk")
+                .json_body_partial(json!({ "offset": offsets[2].to_string() }).to_string());
+            then.status(400).body("invalid proof");
+        });
+        let accepted = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(200);
+        });
+
+        let res = block_on(arweave.upload_transaction_chunks_until_cancelled(
+            &signed_tx,
+            1,
+            CancellationToken::new(),
+            Duration::from_secs(5),
+        ));
+
+        let Err(Error::UploadIncomplete { upload, source }) = res else {
+            panic!("expected an incomplete upload, got {:?}", res);
+        };
+        assert!(matches!(*source, Error::ChunkRejected(_)));
+        assert_eq!(upload.id, signed_tx.id.to_string());
+        assert_eq!(upload.completed, offsets[..2]);
+        assert_eq!(upload.remaining, offsets[2..]);
+        rejected.assert_hits(1);
+        accepted.assert_hits(2);
+    }
+
+    #[test]
+    fn test_query_transactions_pages() {
+        let server = MockServer::start();
+        let owner = Base64(vec![7; 32]);
+        let edge = |cursor: &str, id: u8, height: Option<u64>| {
+            json!({
+                "cursor": cursor,
+                "node": {
+                    "id": Base64(vec![id; 32]).to_string(),
+                    "owner": { "address": owner.to_string() },
+                    "tags": [{ "name": "App-Name", "value": "arweave-rs" }],
+                    "block": height.map(|height| json!({ "height": height })),
+                }
+            })
+        };
+        let page = |edges: Vec<serde_json::Value>, has_next_page: bool| {
+            json!({ "data": { "transactions": {
+                "pageInfo": { "hasNextPage": has_next_page },
+                "edges": edges,
+            } } })
+        };
+        let filters = json!({
+            "tags": [{ "name": "App-Name", "values": ["arweave-rs"] }],
+            "owners": [owner.to_string()],
+        });
+        let first_page = server.mock(|when, then| {
+            when.method(POST).path("/graphql").json_body_partial(
+                json!({ "variables": { "tags": filters["tags"], "owners": filters["owners"], "first": 3, "after": null } })
+                    .to_string(),
+            );
+            then.status(200).json_body(page(
+                vec![edge("c1", 1, Some(1000)), edge("c2", 2, Some(999))],
+                true,
+            ));
+        });
+        let second_page = server.mock(|when, then| {
+            when.method(POST).path("/graphql").json_body_partial(
+                json!({ "variables": { "first": 1, "after": "c2" } }).to_string(),
+            );
+            then.status(200)
+                .json_body(page(vec![edge("c3", 3, None)], true));
+        });
+        let arweave = test_arweave(&server);
+
+        let edges = block_on(arweave.query_transactions(
+            vec![("App-Name".to_string(), vec!["arweave-rs".to_string()])],
+            Some(owner.clone()),
+            3,
+        ))
+        .unwrap();
+
+        first_page.assert_hits(1);
+        second_page.assert_hits(1);
+        assert_eq!(
+            edges.iter().map(|e| e.id.clone()).collect::<Vec<_>>(),
+            (1..=3).map(|id| Base64(vec![id; 32])).collect::<Vec<_>>()
+        );
+        assert_eq!(edges[0].owner, owner);
+        assert_eq!(
+            edges[0].tags,
+            vec![Tag::<String>::from_utf8_strs("App-Name", "arweave-rs").unwrap()]
+        );
+        assert_eq!(edges[1].block_height, Some(999));
+        assert_eq!(edges[2].block_height, None);
+    }
+
+    #[test]
+    fn test_query_transactions_without_filters() {
+        let server = MockServer::start();
+        let recent = server.mock(|when, then| {
+            when.method(POST).path("/graphql").json_body_partial(
+                json!({ "variables": { "tags": null, "owners": null, "first": 100 } }).to_string(),
+            );
+            then.status(200)
+                .json_body(json!({ "data": { "transactions": {
+                "pageInfo": { "hasNextPage": false },
+                "edges": [],
+            } } }));
+        });
+        let arweave = test_arweave(&server);
+
+        let edges = block_on(arweave.query_transactions(vec![], None, 250)).unwrap();
+        recent.assert_hits(1);
+        assert!(edges.is_empty());
+    }
+
+    #[test]
+    fn test_get_transaction_data() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let signed_tx = signed_test_tx(&arweave);
+        let header = serde_json::to_string(&signed_tx.clone_with_no_data().unwrap()).unwrap();
+
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", signed_tx.id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(&header);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", signed_tx.id));
+            then.status(302)
+                .header("Location", server.url("/sandbox/data"));
+        });
+        let mut data = server.mock(|when, then| {
+            when.method(GET).path("/sandbox/data");
+            then.status(200).body("test");
+        });
+
+        let res = block_on(arweave.get_transaction_data(&signed_tx.id, true)).unwrap();
+        assert_eq!(res, b"test");
+
+        data.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/sandbox/data");
+            then.status(200).body("tset");
+        });
+        let res = block_on(arweave.get_transaction_data(&signed_tx.id, false)).unwrap();
+        assert_eq!(res, b"tset");
+        let res = block_on(arweave.get_transaction_data(&signed_tx.id, true));
+        assert!(matches!(
+            res,
+            Err(Error::DataRootMismatch { expected, .. }) if expected == signed_tx.data_root.to_string()
+        ));
+    }
+
+    #[test]
+    fn test_get_data_by_name() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let signed_tx = signed_test_tx(&arweave);
+        let header = serde_json::to_string(&signed_tx.clone_with_no_data().unwrap()).unwrap();
+
+        server.mock(|when, then| {
+            when.method(GET).path("/ar-io/resolver/records/example");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(format!(
+                    r#"{{"txId":"{}","ttlSeconds":3600}}"#,
+                    signed_tx.id
+                ));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/ar-io/resolver/records/unknown");
+            then.status(404);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", signed_tx.id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(&header);
+        });
+        let mut data = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", signed_tx.id));
+            then.status(200).body("test");
+        });
+
+        let res = block_on(arweave.get_data_by_name("example")).unwrap();
+        assert_eq!(res, b"test");
+
+        let res = block_on(arweave.get_data_by_name("unknown"));
+        assert!(matches!(res, Err(Error::NameNotFound(name)) if name == "unknown"));
+
+        data.delete();
+        let mut data = server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", signed_tx.id));
+            then.status(200).body("tset");
+        });
+        let res = block_on(arweave.get_data_by_name("example"));
+        assert!(matches!(res, Err(Error::DataRootMismatch { .. })));
+
+        data.delete();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/{}", signed_tx.id));
+            then.status(404);
+        });
+        let res = block_on(arweave.get_data_by_name("example"));
+        assert!(matches!(res, Err(Error::DataNotFound(id)) if id == signed_tx.id.to_string()));
+    }
+
+    #[test]
+    fn test_download_data_fetches_chunks_in_parallel() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let data: Vec<u8> = (0..4 * 256 * 1024 + 100 * 1024u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+        let tx = Tx::new(
+            arweave.signer.get_provider(),
+            Base64::empty(),
+            data.clone(),
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        let signed_tx = arweave.sign_transaction(tx).unwrap();
+        let header = serde_json::to_string(&signed_tx.clone_with_no_data().unwrap()).unwrap();
+        let start: u64 = 1_000_000_000;
+
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", signed_tx.id));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(&header);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/offset", signed_tx.id));
+            then.status(200).json_body(json!({
+                "offset": (start + data.len() as u64 - 1).to_string(),
+                "size": data.len().to_string(),
+            }));
+        });
+        let chunk_mocks: Vec<_> = (0..signed_tx.chunks.len())
+            .map(|i| {
+                let chunk = signed_tx.get_chunk(i).unwrap();
+                let min = signed_tx.chunks[i].min_byte_range as u64;
+                server.mock(|when, then| {
+                    when.method(GET).path(format!("/chunk/{}", start + min));
+                    then.status(200)
+                        .delay(Duration::from_millis(300))
+                        .json_body(json!({
+                            "chunk": chunk.chunk.to_string(),
+                            "data_path": chunk.data_path.to_string(),
+                            "tx_path": "",
+                        }));
+                })
+            })
+            .collect();
+        assert_eq!(chunk_mocks.len(), 5);
+
+        let started = Instant::now();
+        let downloaded = block_on(arweave.download_data(&signed_tx.id)).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(5 * 300));
+        assert_eq!(downloaded, data);
+        chunk_mocks.iter().for_each(|m| m.assert());
+
+        let mut chunk_mocks = chunk_mocks;
+        chunk_mocks[2].delete();
+        let tampered = signed_tx.get_chunk(3).unwrap();
+        server.mock(|when, then| {
+            let min = signed_tx.chunks[2].min_byte_range as u64;
+            when.method(GET).path(format!("/chunk/{}", start + min));
+            then.status(200).json_body(json!({
+                "chunk": tampered.chunk.to_string(),
+                "data_path": tampered.data_path.to_string(),
+            }));
+        });
+        let res = block_on(arweave.download_data(&signed_tx.id));
+        assert!(matches!(res, Err(Error::InvalidProof)));
     }
 }
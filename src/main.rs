@@ -1,3 +1,5 @@
+#![cfg_attr(target_arch = "wasm32", allow(unused_imports))]
+
 use std::fs;
 use std::{path::PathBuf, str::FromStr};
 
@@ -5,6 +7,11 @@ use arweave_rs::crypto::base64::Base64;
 use arweave_rs::Arweave;
 use url::Url;
 
+// tokio's runtime isn't built for `wasm32`, where the library is used from a browser instead.
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
 async fn main() {
     /* let target = Base64::from_str("PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY").unwrap();
@@ -19,7 +26,7 @@ async fn main() {
             Base64::from_str("PAgdonEn9f5xd-UbYdCX40Sj28eltQVnxz6bbUijeVY").unwrap(),
             vec![],
             vec![],
-            100000,
+            Winston(100000),
             fee,
             false,
         )
@@ -49,7 +56,12 @@ async fn main() {
         Arweave::from_keypair_path(path, Url::from_str("https://arweave.net").unwrap()).unwrap();
     let data = fs::read(file_path.clone()).expect("Could not read file");
 
-    let fee = arweave.get_fee(target, data).await.unwrap();
+    let fee = arweave
+        .get_fee(target, data)
+        .await
+        .unwrap()
+        .try_into()
+        .unwrap();
 
     let res = arweave.upload_file_from_path(file_path, vec![], fee).await;
 
@@ -1,15 +1,10 @@
 use pretend::{pretend, resolver::UrlResolver, JsonResult, Pretend, Url};
 use pretend_reqwest::Client as HttpClient;
-use serde::{Deserialize, Serialize};
 
 use crate::{
     error::Error,
     types::{BlockInfo, NetworkInfo},
 };
-#[derive(Serialize, Deserialize, Debug)]
-struct HeightInfo {
-    height: u64,
-}
 #[pretend]
 trait NetworkInfoFetch {
     #[request(method = "GET", path = "/info")]
@@ -21,6 +16,7 @@ trait NetworkInfoFetch {
     #[request(method = "GET", path = "/block/hash/{id}")]
     async fn block_by_hash(&self, id: &str) -> pretend::Result<JsonResult<BlockInfo, Error>>;
 
+    #[allow(dead_code)]
     #[request(method = "GET", path = "/block/height/{height}")]
     async fn block_by_height(&self, height: u64) -> pretend::Result<JsonResult<BlockInfo, Error>>;
 }
@@ -29,17 +25,24 @@ pub struct NetworkInfoClient(Pretend<HttpClient, UrlResolver>);
 
 impl NetworkInfoClient {
     pub fn new(url: Url) -> Self {
-        let client = HttpClient::default();
-        let pretend = Pretend::for_client(client).with_url(url);
+        Self::with_client(url, reqwest::Client::new())
+    }
+
+    /// Sends requests through `client`, sharing its connection pool.
+    pub fn with_client(url: Url, client: reqwest::Client) -> Self {
+        let pretend = Pretend::for_client(HttpClient::new(client)).with_url(url);
         Self(pretend)
     }
 
+    /// The node's `/info`, e.g. to check that it is on the expected network and synced before
+    /// posting. Fails with [`Error::NetworkInfoError`] if the request fails or the response
+    /// doesn't parse.
     pub async fn network_info(&self) -> Result<NetworkInfo, Error> {
         let response = self
             .0
             .network_info()
             .await
-            .expect("Error getting network info");
+            .map_err(|e| Error::NetworkInfoError(e.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
             JsonResult::Err(err) => Err(err),
@@ -47,7 +50,11 @@ impl NetworkInfoClient {
     }
 
     pub async fn peer_info(&self) -> Result<Vec<String>, Error> {
-        let response = self.0.peer_info().await.expect("Error getting peer info");
+        let response = self
+            .0
+            .peer_info()
+            .await
+            .map_err(|e| Error::NetworkInfoError(e.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
             JsonResult::Err(err) => Err(err),
@@ -59,7 +66,7 @@ impl NetworkInfoClient {
             .0
             .block_by_hash(id)
             .await
-            .expect("Error getting block info");
+            .map_err(|e| Error::NetworkInfoError(e.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
             JsonResult::Err(err) => Err(err),
@@ -71,7 +78,7 @@ impl NetworkInfoClient {
             .0
             .block_by_hash(id)
             .await
-            .expect("Error getting block info");
+            .map_err(|e| Error::NetworkInfoError(e.to_string()))?;
         match response {
             JsonResult::Ok(n) => Ok(n),
             JsonResult::Err(err) => Err(err),
@@ -81,12 +88,60 @@ impl NetworkInfoClient {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{fs, str::FromStr};
 
-    use crate::{crypto::base64::Base64, network::NetworkInfoClient, ARWEAVE_BASE_URL};
+    use crate::{
+        crypto::base64::Base64, error::Error, network::NetworkInfoClient, ARWEAVE_BASE_URL,
+    };
+    use httpmock::{Method::GET, MockServer};
     use pretend::Url;
     use tokio_test::block_on;
 
+    #[test]
+    fn test_network_info_from_fixture() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(fs::read_to_string("res/network_info.json").unwrap());
+        });
+        let client = NetworkInfoClient::new(Url::parse(&server.url("/")).unwrap());
+        let network_info = block_on(client.network_info()).unwrap();
+
+        assert_eq!(network_info.network, "arweave.N.1");
+        assert_eq!(network_info.version, 5);
+        assert_eq!(network_info.height, 1309936);
+        assert_eq!(network_info.blocks, 1309937);
+        assert_eq!(network_info.peers, 5162);
+        assert_eq!(network_info.queue_length, 0);
+    }
+
+    #[test]
+    fn test_network_info_errors() {
+        let server = MockServer::start();
+        let mut garbage = server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(200).body("{\"network\": ");
+        });
+        let client = NetworkInfoClient::new(Url::parse(&server.url("/")).unwrap());
+        let res = block_on(client.network_info());
+        assert!(matches!(res, Err(Error::NetworkInfoError(_))));
+
+        garbage.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/info");
+            then.status(503).body("<html>unavailable</html>");
+        });
+        let res = block_on(client.network_info());
+        assert!(matches!(res, Err(Error::NetworkInfoError(_))));
+
+        // Nothing listens on port 1.
+        let client = NetworkInfoClient::new(Url::parse("http://127.0.0.1:1/").unwrap());
+        let res = block_on(client.network_info());
+        assert!(matches!(res, Err(Error::NetworkInfoError(_))));
+    }
+
     #[test]
     fn test_network_info() {
         let url = Url::parse(ARWEAVE_BASE_URL).unwrap();
@@ -0,0 +1,94 @@
+//! How failed requests to a gateway are retried.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::consts::{CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP};
+
+/// Retry schedule for posting transactions and chunks. The delay before retry `n`, counting
+/// from zero, is `base_delay * 2^n`, capped at `max_delay`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, so a request is sent at most `max_retries + 1` times.
+    pub max_retries: u16,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Waits a random amount between half of and the full delay, so that clients that failed
+    /// together don't all retry at the same time.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    /// [`CHUNKS_RETRIES`] retries, a flat [`CHUNKS_RETRY_SLEEP`] seconds apart.
+    fn default() -> Self {
+        let delay = Duration::from_secs(CHUNKS_RETRY_SLEEP);
+        Self {
+            max_retries: CHUNKS_RETRIES,
+            base_delay: delay,
+            max_delay: delay,
+            jitter: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry `retry`, counting from zero.
+    pub fn delay(&self, retry: u16) -> Duration {
+        let delay = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(retry.into()))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        if !self.jitter || delay.is_zero() {
+            return delay;
+        }
+        let half = delay / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=delay - half)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn test_default_is_flat() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.max_retries, 10);
+        for retry in 0..policy.max_retries {
+            assert_eq!(policy.delay(retry), Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 20,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: false,
+        };
+        let delays: Vec<_> = (0..8).map(|retry| policy.delay(retry)).collect();
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 4000, 8000, 16000, 30000, 30000].map(Duration::from_millis)
+        );
+        assert_eq!(policy.delay(u16::MAX), Duration::from_secs(30));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for retry in 0..20 {
+            let capped = RetryPolicy {
+                jitter: false,
+                ..policy
+            }
+            .delay(retry);
+            let delay = policy.delay(retry);
+            assert!(delay >= capped / 2 && delay <= capped, "{:?}", delay);
+        }
+    }
+}
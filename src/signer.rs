@@ -1,13 +1,12 @@
 use std::path::PathBuf;
 
-use data_encoding::BASE64URL;
-use jsonwebkey::JsonWebKey;
-use rand::thread_rng;
-use rsa::{pkcs8::FromPublicKey, PaddingScheme, PublicKey, RsaPublicKey};
-use sha2::Digest;
-
 use crate::{
-    crypto::{base64::Base64, hash::ToItems, Provider},
+    consts::MESSAGE_SIGNING_PREFIX,
+    crypto::{
+        base64::Base64,
+        hash::{sha256, ToItems},
+        sign, Provider,
+    },
     error::Error,
     transaction::Tx,
 };
@@ -25,71 +24,90 @@ impl Default for ArweaveSigner {
 }
 
 impl ArweaveSigner {
+    /// Verifies a signature produced by [`ArweaveSigner::sign`] over `message`.
     pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), Error> {
-        let crypto = Provider::default();
-        match crypto.verify(pub_key, message, signature) {
-            true => Ok(()),
-            false => Err(Error::InvalidSignature),
+        sign::verify(pub_key, &Self::prefixed_message(message), signature)
+    }
+
+    pub fn new(crypto: Provider) -> Self {
+        Self {
+            crypto: Box::new(crypto),
         }
     }
 
     pub fn from_keypair_path(keypair_path: PathBuf) -> Result<ArweaveSigner, Error> {
-        let crypto = Provider::from_keypair_path(keypair_path);
-        let signer = ArweaveSigner {
-            crypto: Box::new(crypto),
-        };
-        Ok(signer)
+        Ok(Self::new(Provider::from_keypair_path(keypair_path)?))
+    }
+
+    /// Creates a signer from the JSON of a JWK, e.g. read from an environment variable or a
+    /// secret manager rather than a file.
+    pub fn from_keypair_bytes(jwk: &[u8]) -> Result<ArweaveSigner, Error> {
+        Ok(Self::new(Provider::from_keypair_bytes(jwk)?))
     }
 
-    pub fn sign_transaction(&self, mut transaction: Tx) -> Result<Tx, Error> {
-        let deep_hash_item = transaction
-            .to_deep_hash_item()
-            .expect("Could not convert transaction into deep hash item");
+    /// Creates a signer for the wallet with public key modulus `modulus`, whose private key is
+    /// held elsewhere. Transactions built with it have the right owner; sign their
+    /// [`Tx::signature_data`] with the external signer and attach the result with
+    /// [`ArweaveSigner::apply_signature`].
+    pub fn public_only(modulus: &Base64) -> Result<ArweaveSigner, Error> {
+        Ok(Self::new(Provider::public_only(modulus)?))
+    }
+
+    /// Fails with [`Error::NoPrivateKey`] for a signer created with
+    /// [`ArweaveSigner::public_only`].
+    pub fn sign_transaction(&self, transaction: Tx) -> Result<Tx, Error> {
+        let deep_hash_item = transaction.to_deep_hash_item()?;
         let signature_data = self.crypto.deep_hash(deep_hash_item);
-        let signature = self.crypto.sign(&signature_data);
-        let id = self.crypto.hash_sha256(&signature.0);
+        let signature = self.crypto.sign(&signature_data)?;
+        Ok(Self::attach_signature(transaction, signature))
+    }
+
+    /// Attaches `signature`, made over the transaction's [`Tx::signature_data`] elsewhere, and
+    /// sets the id derived from it. Returns [`Error::InvalidSignature`] if it doesn't verify
+    /// against the transaction's owner.
+    pub fn apply_signature(transaction: Tx, signature: Base64) -> Result<Tx, Error> {
+        sign::verify(
+            &transaction.owner.0,
+            &transaction.signature_data()?,
+            &signature.0,
+        )?;
+        Ok(Self::attach_signature(transaction, signature))
+    }
+
+    fn attach_signature(mut transaction: Tx, signature: Base64) -> Tx {
+        transaction.id = Base64(sha256(&signature.0).to_vec());
         transaction.signature = signature;
-        transaction.id = Base64(id.to_vec());
-        Ok(transaction)
+        transaction.posted.set_signed(&transaction.id);
+        transaction
+    }
+
+    /// Signs `message` prefixed with [`MESSAGE_SIGNING_PREFIX`], so the signature can't be
+    /// passed off as a transaction signature. Fails with [`Error::NoPrivateKey`] for a signer
+    /// created with [`ArweaveSigner::public_only`].
+    pub fn sign(&self, message: &[u8]) -> Result<Base64, Error> {
+        self.crypto.sign(&Self::prefixed_message(message))
     }
 
-    pub fn sign(&self, message: &[u8]) -> Base64 {
+    /// Signs `message` as is, without domain separation.
+    ///
+    /// **Warning:** signing a transaction's deep hash with this method produces a valid
+    /// signature for that transaction. Never pass it bytes supplied by a third party; use
+    /// [`ArweaveSigner::sign`] for arbitrary messages.
+    pub fn sign_raw(&self, message: &[u8]) -> Result<Base64, Error> {
         self.crypto.sign(message)
     }
 
+    fn prefixed_message(message: &[u8]) -> Vec<u8> {
+        [MESSAGE_SIGNING_PREFIX, message].concat()
+    }
+
     pub fn verify_transaction(transaction: &Tx) -> Result<(), Error> {
         if transaction.signature.is_empty() {
             return Err(Error::UnsignedTransaction);
         }
 
-        let crypto = Provider::default();
-        let deep_hash_item = transaction
-            .to_deep_hash_item()
-            .expect("Could not convert transaction into deep hash item");
-        let message = crypto.deep_hash(deep_hash_item);
-        let signature = &transaction.signature;
-
-        let jwt_str = format!(
-            "{{\"kty\":\"RSA\",\"e\":\"AQAB\",\"n\":\"{}\"}}",
-            BASE64URL.encode(&transaction.owner.0)
-        );
-        let jwk: JsonWebKey = jwt_str.parse().unwrap();
-
-        let pub_key = RsaPublicKey::from_public_key_der(jwk.key.to_der().as_slice()).unwrap();
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(&message);
-        let hashed = &hasher.finalize();
-
-        let rng = thread_rng();
-        let padding = PaddingScheme::PSS {
-            salt_rng: Box::new(rng),
-            digest: Box::new(sha2::Sha256::new()),
-            salt_len: None,
-        };
-        pub_key
-            .verify(padding, hashed.as_ref(), &signature.0)
-            .map(|_| ())
-            .map_err(|_| Error::InvalidSignature)
+        let message = transaction.signature_data()?;
+        sign::verify(&transaction.owner.0, &message, &transaction.signature.0)
     }
 
     pub fn wallet_address(&self) -> Base64 {
@@ -111,7 +129,13 @@ impl ArweaveSigner {
 
 #[cfg(test)]
 mod tests {
-    use crate::error::Error;
+    use std::str::FromStr;
+
+    use crate::{
+        crypto::hash::{deep_hash, ToItems},
+        error::Error,
+        transaction::Tx,
+    };
 
     use super::{ArweaveSigner, Base64};
 
@@ -126,8 +150,71 @@ mod tests {
             .to_vec(),
         );
         let signer = ArweaveSigner::default();
-        let signature = signer.sign(&message.0);
+        let signature = signer.sign(&message.0)?;
         let pubk = signer.get_public_key();
         ArweaveSigner::verify(&pubk.0, &message.0, &signature.0)
     }
+
+    #[test]
+    fn test_sign_with_public_only_signer() -> Result<(), Error> {
+        let signer = ArweaveSigner::default();
+        let public = ArweaveSigner::public_only(&signer.keypair_modulus())?;
+        let tx = Tx::new(
+            public.get_provider(),
+            Base64::empty(),
+            b"remote".to_vec(),
+            0,
+            10,
+            Base64(vec![1; 32]),
+            vec![],
+            false,
+        )?;
+        assert_eq!(tx.owner, signer.keypair_modulus());
+
+        assert!(matches!(
+            public.sign_transaction(Tx::from_str(&serde_json::to_string(&tx).unwrap())?),
+            Err(Error::NoPrivateKey)
+        ));
+        assert!(matches!(public.sign(b"message"), Err(Error::NoPrivateKey)));
+        assert!(matches!(
+            public.sign_raw(b"message"),
+            Err(Error::NoPrivateKey)
+        ));
+
+        // The "remote" signer signs the signature data.
+        let signature = signer.sign_raw(&tx.signature_data()?)?;
+        let signed = ArweaveSigner::apply_signature(tx, signature)?;
+        ArweaveSigner::verify_transaction(&signed)?;
+        assert_eq!(signed.id.0.len(), 32);
+
+        let res = ArweaveSigner::apply_signature(signed, Base64(vec![0; 512]));
+        assert!(matches!(res, Err(Error::InvalidSignature)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_message_is_not_a_transaction_signature() -> Result<(), Error> {
+        let signer = ArweaveSigner::default();
+        let mut tx = Tx::new(
+            signer.get_provider(),
+            Base64::empty(),
+            b"message".to_vec(),
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )?;
+        let message = deep_hash(tx.to_deep_hash_item()?);
+
+        tx.signature = signer.sign(&message)?;
+        assert!(matches!(
+            ArweaveSigner::verify_transaction(&tx),
+            Err(Error::InvalidSignature)
+        ));
+
+        tx.signature = signer.sign_raw(&message)?;
+        assert!(ArweaveSigner::verify_transaction(&tx).is_ok());
+        Ok(())
+    }
 }
@@ -0,0 +1,266 @@
+//! Named-argument front end to [`Arweave::create_transaction`], started with
+//! [`Arweave::transaction_builder`].
+
+use crate::{
+    crypto::base64::Base64,
+    currency::Winston,
+    error::Error,
+    transaction::{
+        generator::{app_tags, merge_tags},
+        tags::{FromUtf8Strs, Tag},
+        Tx,
+    },
+    Arweave,
+};
+
+/// Builds an unsigned transaction. Everything is optional: by default the transaction has no
+/// target, data or tags, transfers nothing, pays the fee the gateway asks for, is anchored to
+/// the gateway's current anchor and gets a `Content-Type` tag sniffed from the data and the
+/// [`app_tags`] unless tags of those names are added with [`TxBuilder::tag`].
+#[derive(Clone)]
+pub struct TxBuilder<'a> {
+    arweave: &'a Arweave,
+    target: Base64,
+    data: Vec<u8>,
+    tags: Vec<(String, String)>,
+    quantity: Winston,
+    fee: Option<Winston>,
+    auto_content_tag: bool,
+    app_tags: bool,
+    last_tx: Option<Base64>,
+}
+
+impl<'a> TxBuilder<'a> {
+    pub(crate) fn new(arweave: &'a Arweave) -> Self {
+        Self {
+            arweave,
+            target: Base64::empty(),
+            data: Vec::new(),
+            tags: Vec::new(),
+            quantity: Winston(0),
+            fee: None,
+            auto_content_tag: true,
+            app_tags: true,
+            last_tx: None,
+        }
+    }
+
+    /// Wallet address `quantity` is transferred to.
+    pub fn target(mut self, target: Base64) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Adds a tag. Tags keep the order they are added in.
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        self.tags.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Amount transferred to the target.
+    pub fn quantity(mut self, quantity: Winston) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    /// Reward paid to miners, instead of the fee fetched from the gateway.
+    pub fn fee(mut self, fee: Winston) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    /// Whether to add a `Content-Type` tag sniffed from the data when none was added.
+    pub fn auto_content_tag(mut self, auto_content_tag: bool) -> Self {
+        self.auto_content_tag = auto_content_tag;
+        self
+    }
+
+    /// Whether to add the [`app_tags`] naming this crate when no tags of the same names were
+    /// added.
+    pub fn app_tags(mut self, app_tags: bool) -> Self {
+        self.app_tags = app_tags;
+        self
+    }
+
+    /// Anchor to use instead of fetching one from the gateway. Together with [`TxBuilder::fee`]
+    /// this makes [`TxBuilder::build`] send no requests, e.g. for building on an offline
+    /// machine with an anchor fetched elsewhere.
+    pub fn last_tx(mut self, last_tx: Base64) -> Self {
+        self.last_tx = Some(last_tx);
+        self
+    }
+
+    pub async fn build(&self) -> Result<Tx, Error> {
+        let tags = self
+            .tags
+            .iter()
+            .map(|(name, value)| Tag::from_utf8_strs(name, value))
+            .collect::<Result<Vec<Tag<Base64>>, Error>>()?;
+        let tags = match self.app_tags {
+            true => merge_tags(tags, app_tags()?),
+            false => tags,
+        };
+        let anchor = match &self.last_tx {
+            Some(last_tx) => last_tx.clone(),
+            None => self.arweave.anchor().await?,
+        };
+        let fee = self
+            .arweave
+            .fee_or_price(self.fee.unwrap_or_default(), self.data.len(), &self.target)
+            .await?;
+        self.arweave.create_transaction_with_anchor(
+            self.target.clone(),
+            tags,
+            self.data.clone(),
+            self.quantity,
+            fee,
+            self.auto_content_tag,
+            anchor,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use httpmock::{Method::GET, MockServer};
+    use pretend::Url;
+    use tokio_test::block_on;
+
+    use crate::{
+        consts::VERSION,
+        crypto::base64::Base64,
+        currency::Winston,
+        transaction::tags::{FromUtf8Strs, Tag},
+        Arweave,
+    };
+
+    use super::TxBuilder;
+
+    fn test_arweave(server: &MockServer) -> Arweave {
+        Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet.json").unwrap(),
+            Url::parse(&server.url("/")).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_defaults_fetch_anchor_and_fee() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/tx_anchor");
+            then.status(200).body(Base64(vec![9; 32]).to_string());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/price/5");
+            then.status(200).body("1234");
+        });
+        let arweave = test_arweave(&server);
+
+        let tx = block_on(
+            arweave
+                .transaction_builder()
+                .data(b"hello".to_vec())
+                .tag("Content-Type", "text/plain")
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(tx.last_tx, Base64(vec![9; 32]));
+        assert_eq!(tx.reward, 1234);
+        assert_eq!(tx.quantity, 0u128.into());
+        assert!(tx.target.is_empty());
+        let content_type = Tag::<Base64>::from_utf8_strs("Content-Type", "text/plain").unwrap();
+        assert_eq!(
+            tx.tags
+                .iter()
+                .filter(|tag| tag.name == content_type.name)
+                .collect::<Vec<_>>(),
+            vec![&content_type]
+        );
+    }
+
+    #[test]
+    fn test_tags_merge_with_generated_ones() {
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let build = |builder: TxBuilder| {
+            block_on(
+                builder
+                    .data(b"{\"a\": 1}".to_vec())
+                    .fee(Winston(1))
+                    .last_tx(Base64(vec![1; 32]))
+                    .build(),
+            )
+            .unwrap()
+            .tags_as_strings()
+        };
+        let tag = |name: &str, value: &str| (name.to_string(), value.to_string());
+
+        let tags = build(
+            arweave
+                .transaction_builder()
+                .tag("Content-Type", "text/html")
+                .tag("App-Name", "mine")
+                .tag("Page", "1")
+                .tag("Page", "1"),
+        );
+        assert_eq!(
+            tags,
+            vec![
+                tag("Content-Type", "text/html"),
+                tag("App-Name", "mine"),
+                tag("Page", "1"),
+                tag("Page", "1"),
+                tag("App-Version", VERSION),
+                tag("User-Agent", &format!("arweave-rs/{}", VERSION)),
+            ]
+        );
+
+        let tags = build(arweave.transaction_builder().app_tags(false));
+        assert_eq!(
+            tags,
+            vec![
+                tag("User-Agent", &format!("arweave-rs/{}", VERSION)),
+                tag("Content-Type", "application/json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explicit_fields_skip_requests() {
+        // Nothing is mocked, so any request would fail.
+        let server = MockServer::start();
+        let arweave = test_arweave(&server);
+        let target = Base64(vec![5; 32]);
+
+        let tx = block_on(
+            arweave
+                .transaction_builder()
+                .target(target.clone())
+                .quantity(Winston(100_000))
+                .fee(Winston(600_912))
+                .last_tx(Base64(vec![1; 48]))
+                .tag("App-Name", "arweave-rs")
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(tx.target, target);
+        assert_eq!(tx.quantity, 100_000u128.into());
+        assert_eq!(tx.get_tag("App-Name").as_deref(), Some("arweave-rs"));
+        assert_eq!(tx.get_tag("App-Version").as_deref(), Some(VERSION));
+        assert_eq!(tx.reward, 600_912);
+        assert_eq!(tx.last_tx, Base64(vec![1; 48]));
+        assert!(tx
+            .tags
+            .contains(&Tag::<Base64>::from_utf8_strs("App-Name", "arweave-rs").unwrap()));
+    }
+}
@@ -1,36 +1,72 @@
-use reqwest::{
-    header::{ACCEPT, CONTENT_TYPE},
-    StatusCode,
-};
+use reqwest::{header::RANGE, StatusCode};
+use serde::de::DeserializeOwned;
 use serde_json::json;
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{str::FromStr, sync::Arc};
 
 use crate::{
-    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    clock::{Clock, SystemClock},
+    consts::{ARWEAVE_BASE_URL, QUEUE_FULL_RETRIES},
     crypto::base64::Base64,
     error::Error,
-    types::TxStatus,
+    http::{HttpClient, HttpError, HttpResponse, ReqwestClient},
+    retry::RetryPolicy,
+    types::{
+        ChunkData, ChunkUploadStatus, TransactionStatus, TransactionsQueryResponse, TxOffset,
+        TxStatus,
+    },
 };
 
 use super::Tx;
 
 pub struct TxClient {
     client: reqwest::Client,
+    http: Arc<dyn HttpClient>,
     base_url: url::Url,
+    clock: Arc<dyn Clock>,
+    retry_policy: RetryPolicy,
 }
 
 impl Default for TxClient {
     fn default() -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            base_url: url::Url::from_str(ARWEAVE_BASE_URL).unwrap(),
-        }
+        Self::new(
+            reqwest::Client::new(),
+            url::Url::from_str(ARWEAVE_BASE_URL).unwrap(),
+        )
+        .unwrap()
     }
 }
 
 impl TxClient {
     pub fn new(client: reqwest::Client, base_url: url::Url) -> Result<Self, Error> {
-        Ok(Self { client, base_url })
+        Ok(Self {
+            http: Arc::new(ReqwestClient(client.clone())),
+            client,
+            base_url,
+            clock: Arc::new(SystemClock),
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sends requests through `http` instead of the `reqwest` client, e.g. a `MockHttpClient`
+    /// in tests. Range requests still use the `reqwest` client.
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Self {
+        self.http = http;
+        self
+    }
+
+    async fn get(&self, path: &str, error: fn(String) -> Error) -> Result<HttpResponse, Error> {
+        let url = self.base_url.join(path).map_err(|e| error(e.to_string()))?;
+        self.http.get(url).await.map_err(|e| e.into_error(error))
     }
 
     pub async fn post_transaction(&self, signed_transaction: &Tx) -> Result<(Base64, u64), Error> {
@@ -38,117 +74,567 @@ impl TxClient {
             return Err(Error::UnsignedTransaction);
         }
 
-        let mut retries = 0;
-        let mut status = reqwest::StatusCode::NOT_FOUND;
         let url = self
             .base_url
             .join("tx")
             .expect("Could not join base_url with /tx");
+        let tx_json = serde_json::to_vec(signed_transaction)?;
 
         dbg!(json!(signed_transaction));
-        while (retries < CHUNKS_RETRIES) & (status != reqwest::StatusCode::OK) {
-            let res = self
-                .client
-                .post(url.clone())
-                .json(&signed_transaction)
-                .header(&ACCEPT, "application/json")
-                .header(&CONTENT_TYPE, "application/json")
-                .send()
-                .await
-                .expect("Could not post transaction");
-            status = res.status();
-            dbg!(status);
-            if status == reqwest::StatusCode::OK {
-                return Ok((signed_transaction.id.clone(), signed_transaction.reward));
+        // One attempt and then up to `max_retries` more, as for chunks.
+        let mut retries = 0;
+        let mut queue_full_retries = 0;
+        loop {
+            let err = match self.http.post_json(url.clone(), &tx_json).await {
+                Ok(res) if res.status == reqwest::StatusCode::OK => {
+                    signed_transaction.posted.set_posted();
+                    return Ok((signed_transaction.id.clone(), signed_transaction.reward));
+                }
+                Ok(res) => {
+                    let status = res.status;
+                    dbg!(status);
+                    let body = res.text();
+                    // Retrying can't help once the anchor is too old, the transaction has to be
+                    // signed again with a fresh one.
+                    if status == reqwest::StatusCode::BAD_REQUEST && is_invalid_anchor(&body) {
+                        return Err(Error::AnchorExpired(body));
+                    }
+                    if status == reqwest::StatusCode::SERVICE_UNAVAILABLE && is_queue_full(&body) {
+                        if queue_full_retries == QUEUE_FULL_RETRIES {
+                            return Err(Error::GatewayQueueFull);
+                        }
+                        queue_full_retries += 1;
+                        Error::GatewayQueueFull
+                    } else {
+                        Error::StatusCodeNotOk {
+                            status: status.as_u16(),
+                            body,
+                        }
+                    }
+                }
+                // A connection failure on the last attempt is reported as such rather than as a
+                // bad status.
+                Err(e) => e.into_error(Error::ArweavePostError),
+            };
+            if retries >= self.retry_policy.max_retries {
+                return Err(err);
             }
-            sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
+            self.clock.sleep(self.retry_policy.delay(retries)).await;
             retries += 1;
         }
-
-        Err(Error::StatusCodeNotOk)
     }
 
-    pub async fn get_last_tx(&self) -> Base64 {
-        let resp = self
-            .client
-            .get(
-                self.base_url
-                    .join("tx_anchor")
-                    .expect("Could not join base_url with /tx_anchor"),
-            )
-            .send()
-            .await
-            .expect("Could not get last tx");
-        let last_tx_str = resp.text().await.unwrap();
-        Base64::from_str(&last_tx_str).unwrap()
+    pub async fn get_last_tx(&self) -> Result<Base64, Error> {
+        let resp = self.get("tx_anchor", Error::AnchorUnavailable).await?;
+        if resp.status != StatusCode::OK {
+            return Err(Error::AnchorUnavailable(format!(
+                "gateway returned status {}",
+                resp.status
+            )));
+        }
+        let last_tx_str = resp.text();
+        Base64::from_str(last_tx_str.trim()).map_err(|_| Error::InvalidAnchor(last_tx_str))
     }
 
     pub async fn get_fee(&self, target: Base64, data: Vec<u8>) -> Result<u64, Error> {
-        let url = self
-            .base_url
-            .join(&format!("price/{}/{}", data.len(), target))
-            .expect("Could not join base_url with /price/{}/{}");
-        let winstons_per_bytes = reqwest::get(url)
-            .await
-            .map_err(|e| Error::GetPriceError(e.to_string()))?
+        self.get_price(data.len() as u64, Some(&target)).await
+    }
+
+    /// Fee in winstons for a transaction carrying `data_size` bytes, from `price/{data_size}`,
+    /// or `price/{data_size}/{target}` for a transfer to `target`. An empty `target` is the same
+    /// as `None`.
+    pub async fn get_price(&self, data_size: u64, target: Option<&Base64>) -> Result<u64, Error> {
+        let path = match target.filter(|target| !target.is_empty()) {
+            Some(target) => format!("price/{}/{}", data_size, target),
+            None => format!("price/{}", data_size),
+        };
+        let winstons_per_bytes = self
+            .get(&path, Error::GetPriceError)
+            .await?
             .json::<u64>()
-            .await
-            .expect("Could not get base fee");
+            .map_err(|e| Error::GetPriceError(e.to_string()))?;
 
         Ok(winstons_per_bytes)
     }
 
     pub async fn get_tx(&self, id: Base64) -> Result<(StatusCode, Option<Tx>), Error> {
         let res = self
-            .client
-            .get(
-                self.base_url
-                    .join(&format!("tx/{}", id))
-                    .expect("Could not join base_url with /tx"),
-            )
-            .send()
-            .await
-            .expect("Could not get tx");
+            .get(&format!("tx/{}", id), Error::TransactionInfoError)
+            .await?;
 
-        if res.status() == StatusCode::OK {
-            let text = res
-                .text()
-                .await
-                .expect("Could not parse response to string");
-            let tx = Tx::from_str(&text).expect("Could not create Tx from string");
+        if res.status == StatusCode::OK {
+            let tx = Tx::from_str(&res.text())?;
             return Ok((StatusCode::OK, Some(tx)));
-        } else if res.status() == StatusCode::ACCEPTED {
+        } else if res.status == StatusCode::ACCEPTED {
             //Tx is pending
             return Ok((StatusCode::ACCEPTED, None));
         }
 
-        Err(Error::TransactionInfoError(res.status().to_string()))
+        Err(Error::TransactionInfoError(res.status.to_string()))
     }
 
-    pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
+    /// The transaction `id` from `tx/{id}`. Returns [`Error::TransactionNotFound`] for a 404 and
+    /// [`Error::TransactionPending`] for a 202, and checks that the gateway served the
+    /// transaction that was asked for.
+    pub async fn get_transaction(&self, id: &Base64) -> Result<Tx, Error> {
+        let res = self
+            .get(&format!("tx/{}", id), Error::TransactionInfoError)
+            .await?;
+
+        match res.status {
+            StatusCode::OK => {
+                let tx = Tx::from_str(&res.text())?;
+                if tx.id != *id {
+                    return Err(Error::TransactionInfoError(format!(
+                        "gateway returned transaction {} for {}",
+                        tx.id, id
+                    )));
+                }
+                Ok(tx)
+            }
+            StatusCode::ACCEPTED => Err(Error::TransactionPending(id.to_string())),
+            StatusCode::NOT_FOUND => Err(Error::TransactionNotFound(id.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Raw data of the transaction, as served from the gateway's `/{id}` endpoint.
+    pub async fn get_data(&self, id: &Base64) -> Result<Vec<u8>, Error> {
+        let res = self
+            .get(&id.to_string(), Error::TransactionInfoError)
+            .await?;
+
+        match res.status {
+            StatusCode::OK => Ok(res.body),
+            StatusCode::NOT_FOUND => Err(Error::DataNotFound(id.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Bytes `start..end` of the transaction's data, requested with an HTTP `Range` header.
+    /// Returns [`Error::RangeUnsupported`] rather than the whole data if the gateway ignores
+    /// the range.
+    pub async fn get_data_range(
+        &self,
+        id: &Base64,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, Error> {
+        if start >= end {
+            return Ok(Vec::new());
+        }
         let res = self
             .client
             .get(
                 self.base_url
-                    .join(&format!("tx/{}/status", id))
-                    .expect("Could not join base_url with /tx/{}/status"),
+                    .join(&id.to_string())
+                    .expect("Could not join base_url with /{id}"),
             )
+            .header(RANGE, format!("bytes={}-{}", start, end - 1))
             .send()
             .await
-            .expect("Could not get tx status");
+            .map_err(|e| HttpError::from(e).into_error(Error::TransactionInfoError))?;
+
+        match res.status() {
+            StatusCode::PARTIAL_CONTENT => Ok(res
+                .bytes()
+                .await
+                .map_err(|e| HttpError::from(e).into_error(Error::TransactionInfoError))?
+                .to_vec()),
+            StatusCode::OK => Err(Error::RangeUnsupported),
+            StatusCode::RANGE_NOT_SATISFIABLE => Err(Error::SliceError),
+            StatusCode::NOT_FOUND => Err(Error::DataNotFound(id.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Where the transaction's data sits in the weave, or `None` if the gateway doesn't know.
+    pub async fn get_tx_offset(&self, id: &Base64) -> Result<Option<TxOffset>, Error> {
+        let res = self
+            .get(&format!("tx/{}/offset", id), Error::TransactionInfoError)
+            .await?;
+
+        match res.status {
+            StatusCode::OK => res
+                .json()
+                .map(Some)
+                .map_err(|e| Error::TransactionInfoError(e.to_string())),
+            StatusCode::NOT_FOUND => Ok(None),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Ids of the transactions matched by a GraphQL `transactions` `query` that selects
+    /// `edges { node { id } }`.
+    pub async fn graphql_transactions(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<Vec<Base64>, Error> {
+        Ok(self
+            .graphql::<TransactionsQueryResponse>(query, variables)
+            .await?
+            .data
+            .transactions
+            .edges
+            .into_iter()
+            .map(|edge| edge.node.id)
+            .collect())
+    }
+
+    /// Posts a GraphQL `query` to the gateway's `/graphql` endpoint and deserializes the
+    /// response into `T`.
+    pub async fn graphql<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, Error> {
+        let url = self
+            .base_url
+            .join("graphql")
+            .expect("Could not join base_url with /graphql");
+        let body = json!({ "query": query, "variables": variables }).to_string();
+        let res = self
+            .http
+            .post_json(url, body.as_bytes())
+            .await
+            .map_err(|e| e.into_error(Error::TransactionInfoError))?;
+
+        match res.status {
+            StatusCode::OK => res
+                .json::<T>()
+                .map_err(|e| Error::TransactionInfoError(e.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Height of the gateway's current block.
+    pub async fn get_height(&self) -> Result<u64, Error> {
+        let res = self.get("height", Error::NetworkInfoError).await?;
 
-        if res.status() == StatusCode::OK {
+        match res.status {
+            StatusCode::OK => res
+                .json()
+                .map_err(|e| Error::NetworkInfoError(e.to_string())),
+            status => Err(Error::NetworkInfoError(status.to_string())),
+        }
+    }
+
+    /// Chunks of the transaction the gateway has accepted, or `None` if the gateway doesn't
+    /// support the pending chunks endpoint.
+    pub async fn get_chunk_upload_status(
+        &self,
+        id: &Base64,
+    ) -> Result<Option<ChunkUploadStatus>, Error> {
+        let res = self
+            .get(
+                &format!("tx/{}/pending_chunks", id),
+                Error::TransactionInfoError,
+            )
+            .await?;
+
+        match res.status {
+            StatusCode::OK => res
+                .json()
+                .map(Some)
+                .map_err(|e| Error::TransactionInfoError(e.to_string())),
+            StatusCode::NOT_FOUND | StatusCode::NOT_IMPLEMENTED => Ok(None),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Ids of the transactions in the gateway's mempool, from `tx/pending`.
+    pub async fn get_pending_transactions(&self) -> Result<Vec<Base64>, Error> {
+        let res = self.get("tx/pending", Error::TransactionInfoError).await?;
+
+        match res.status {
+            StatusCode::OK => res
+                .json()
+                .map_err(|e| Error::TransactionInfoError(e.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Chunk containing the byte at absolute weave `offset`.
+    pub async fn get_chunk(&self, offset: u64) -> Result<ChunkData, Error> {
+        let res = self
+            .get(&format!("chunk/{}", offset), Error::TransactionInfoError)
+            .await?;
+
+        match res.status {
+            StatusCode::OK => res
+                .json()
+                .map_err(|e| Error::TransactionInfoError(e.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    /// Status of `id`: 200 is confirmed, 202 pending and 404 not found.
+    pub async fn get_transaction_status(&self, id: &Base64) -> Result<TransactionStatus, Error> {
+        let res = self
+            .get(&format!("tx/{}/status", id), Error::TransactionInfoError)
+            .await?;
+
+        match res.status {
+            StatusCode::OK => res
+                .json::<TxStatus>()
+                .map(TransactionStatus::Confirmed)
+                .map_err(|e| Error::TransactionInfoError(e.to_string())),
+            StatusCode::ACCEPTED => Ok(TransactionStatus::Pending),
+            StatusCode::NOT_FOUND => Err(Error::TransactionNotFound(id.to_string())),
+            status => Err(Error::TransactionInfoError(status.to_string())),
+        }
+    }
+
+    pub async fn get_tx_status(&self, id: Base64) -> Result<(StatusCode, Option<TxStatus>), Error> {
+        let res = self
+            .get(&format!("tx/{}/status", id), Error::TransactionInfoError)
+            .await?;
+
+        if res.status == StatusCode::OK {
             let status = res
                 .json::<TxStatus>()
-                .await
-                .map_err(|err| Error::TransactionInfoError(err.to_string()))
-                .expect("Could not parse tx status");
+                .map_err(|e| Error::TransactionInfoError(e.to_string()))?;
 
             Ok((StatusCode::OK, Some(status)))
-        } else if res.status() == StatusCode::ACCEPTED {
+        } else if res.status == StatusCode::ACCEPTED {
             Ok((StatusCode::ACCEPTED, None))
         } else {
-            Err(Error::TransactionInfoError(res.status().to_string()))
+            Err(Error::TransactionInfoError(res.status.to_string()))
+        }
+    }
+}
+
+/// Whether a 503 body from `tx/` is the node reporting its mempool full, rather than some other
+/// outage.
+fn is_queue_full(body: &str) -> bool {
+    let body = body.to_lowercase();
+    body.contains("mempool") || body.contains("queue full") || body.contains("queue is full")
+}
+
+/// Whether a 400 body from `tx/` is the node rejecting the transaction's `last_tx`.
+fn is_invalid_anchor(body: &str) -> bool {
+    let body = body.to_lowercase();
+    body.contains("invalid_anchor") || body.contains("invalid anchor")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+    use reqwest::StatusCode;
+    use tokio_test::block_on;
+
+    use crate::{
+        clock::TestClock,
+        consts::ARWEAVE_BASE_URL,
+        crypto::base64::Base64,
+        error::Error,
+        http::{HttpError, HttpResponse, MockHttpClient},
+        retry::RetryPolicy,
+        transaction::Tx,
+        ArweaveSigner,
+    };
+
+    use super::TxClient;
+
+    fn mock_client(http: Arc<MockHttpClient>, clock: Arc<TestClock>) -> TxClient {
+        TxClient::new(
+            reqwest::Client::new(),
+            url::Url::from_str(ARWEAVE_BASE_URL).unwrap(),
+        )
+        .unwrap()
+        .with_http_client(http)
+        .with_clock(clock)
+    }
+
+    fn signed_tx() -> Tx {
+        let signer =
+            ArweaveSigner::from_keypair_path(PathBuf::from("res/test_wallet.json")).unwrap();
+        let tx = Tx::new(
+            signer.get_provider(),
+            Base64::empty(),
+            b"test".to_vec(),
+            0,
+            1000,
+            Base64(vec![1; 32]),
+            vec![],
+            false,
+        )
+        .unwrap();
+        signer.sign_transaction(tx).unwrap()
+    }
+
+    #[test]
+    fn test_post_transaction_retries_until_accepted() {
+        let http = Arc::new(MockHttpClient::new());
+        http.push_response(Ok(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, "")));
+        http.push_response(Ok(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, "")));
+        http.push_response(Ok(HttpResponse::new(StatusCode::OK, "OK")));
+        let clock = Arc::new(TestClock::new());
+        let client = mock_client(http.clone(), clock.clone()).with_retry_policy(RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: false,
+        });
+        let tx = signed_tx();
+
+        let (id, reward) = block_on(client.post_transaction(&tx)).unwrap();
+
+        assert_eq!((id, reward), (tx.id.clone(), 1000));
+        assert_eq!(
+            clock.sleeps(),
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+        let requests = http.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|(method, url)| *method == "POST" && url.path() == "/tx"));
+    }
+
+    #[test]
+    fn test_post_transaction_gives_up_after_max_retries() {
+        let http = Arc::new(MockHttpClient::new());
+        for _ in 0..4 {
+            http.push_response(Ok(HttpResponse::new(
+                StatusCode::BAD_GATEWAY,
+                "invalid anchor",
+            )));
+        }
+        let clock = Arc::new(TestClock::new());
+        let client = mock_client(http.clone(), clock.clone()).with_retry_policy(RetryPolicy {
+            max_retries: 3,
+            ..Default::default()
+        });
+
+        let res = block_on(client.post_transaction(&signed_tx()));
+
+        assert!(
+            matches!(res, Err(Error::StatusCodeNotOk { status: 502, ref body }) if body == "invalid anchor")
+        );
+        assert_eq!(http.requests().len(), 4);
+        assert_eq!(clock.sleeps().len(), 3);
+    }
+
+    #[test]
+    fn test_post_transaction_without_retries() {
+        let http = Arc::new(MockHttpClient::new());
+        http.push_response(Ok(HttpResponse::new(StatusCode::BAD_GATEWAY, "down")));
+        let clock = Arc::new(TestClock::new());
+        let client = mock_client(http.clone(), clock.clone()).with_retry_policy(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        });
+
+        let res = block_on(client.post_transaction(&signed_tx()));
+
+        assert!(
+            matches!(res, Err(Error::StatusCodeNotOk { status: 502, ref body }) if body == "down")
+        );
+        assert_eq!(http.requests().len(), 1);
+        assert!(clock.sleeps().is_empty());
+    }
+
+    #[test]
+    fn test_get_transaction() {
+        let sample_tx = std::fs::read_to_string("res/sample_tx.json").unwrap();
+        let id = Base64::from_str("t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU").unwrap();
+        let http = Arc::new(MockHttpClient::new());
+        http.push_response(Ok(HttpResponse::new(StatusCode::OK, sample_tx.clone())));
+        http.push_response(Ok(HttpResponse::new(StatusCode::ACCEPTED, "Pending")));
+        http.push_response(Ok(HttpResponse::new(StatusCode::NOT_FOUND, "Not Found")));
+        http.push_response(Ok(HttpResponse::new(StatusCode::OK, sample_tx.clone())));
+        let client = mock_client(http.clone(), Arc::new(TestClock::new()));
+
+        let tx = block_on(client.get_transaction(&id)).unwrap();
+        assert_eq!(tx, Tx::from_str(&sample_tx).unwrap());
+        assert!(ArweaveSigner::verify_transaction(&tx).is_ok());
+        assert!(matches!(
+            block_on(client.get_transaction(&id)),
+            Err(Error::TransactionPending(_))
+        ));
+        assert!(matches!(
+            block_on(client.get_transaction(&id)),
+            Err(Error::TransactionNotFound(_))
+        ));
+        // A different transaction than the one asked for is rejected.
+        assert!(matches!(
+            block_on(client.get_transaction(&Base64(vec![1; 32]))),
+            Err(Error::TransactionInfoError(_))
+        ));
+        assert_eq!(http.requests()[0].1.path(), format!("/tx/{}", id));
+    }
+
+    #[test]
+    fn test_get_pending_transactions() {
+        let pending = vec![Base64(vec![1; 32]), Base64(vec![2; 32])];
+        let http = Arc::new(MockHttpClient::new());
+        http.push_response(Ok(HttpResponse::new(
+            StatusCode::OK,
+            serde_json::to_vec(&pending).unwrap(),
+        )));
+        http.push_response(Ok(HttpResponse::new(StatusCode::OK, "[1]")));
+        http.push_response(Ok(HttpResponse::new(StatusCode::BAD_GATEWAY, "")));
+        let client = mock_client(http.clone(), Arc::new(TestClock::new()));
+
+        assert_eq!(
+            block_on(client.get_pending_transactions()).unwrap(),
+            pending
+        );
+        for _ in 0..2 {
+            assert!(matches!(
+                block_on(client.get_pending_transactions()),
+                Err(Error::TransactionInfoError(_))
+            ));
         }
+        assert_eq!(http.requests()[0].1.path(), "/tx/pending");
+    }
+
+    #[test]
+    fn test_get_price_paths() {
+        let http = Arc::new(MockHttpClient::new());
+        for _ in 0..3 {
+            http.push_response(Ok(HttpResponse::new(StatusCode::OK, "1000")));
+        }
+        let client = mock_client(http.clone(), Arc::new(TestClock::new()));
+        let target = Base64(vec![5; 32]);
+
+        for target in [None, Some(&Base64::empty()), Some(&target)] {
+            assert_eq!(block_on(client.get_price(10, target)).unwrap(), 1000);
+        }
+        let paths: Vec<String> = http
+            .requests()
+            .iter()
+            .map(|(_, url)| url.path().to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            ["/price/10", "/price/10", &format!("/price/10/{}", target)]
+        );
+    }
+
+    #[test]
+    fn test_get_last_tx() {
+        let http = Arc::new(MockHttpClient::new());
+        http.push_response(Ok(HttpResponse::new(
+            StatusCode::OK,
+            Base64(vec![7; 32]).to_string(),
+        )));
+        http.push_response(Ok(HttpResponse::new(StatusCode::OK, "<html>")));
+        http.push_response(Err(HttpError::Timeout("timed out".to_string())));
+        let client = mock_client(http.clone(), Arc::new(TestClock::new()));
+
+        assert_eq!(block_on(client.get_last_tx()).unwrap(), Base64(vec![7; 32]));
+        assert!(matches!(
+            block_on(client.get_last_tx()),
+            Err(Error::InvalidAnchor(_))
+        ));
+        assert!(matches!(
+            block_on(client.get_last_tx()),
+            Err(Error::Timeout(_))
+        ));
+        assert_eq!(http.requests()[0].1.path(), "/tx_anchor");
     }
 }
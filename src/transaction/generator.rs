@@ -0,0 +1,448 @@
+//! Builds the unsigned transactions returned by [`crate::Arweave::create_transaction`]. The
+//! generator is swappable so that tests can produce transactions with known contents.
+
+use crate::{
+    bundle::DataItem,
+    consts::VERSION,
+    crypto::{base64::Base64, Provider},
+    error::Error,
+    transaction::{
+        tags::{FromUtf8Strs, Tag},
+        Tx,
+    },
+};
+
+/// Tags of a new transaction: `tags` as passed, in order and including repeated names, which
+/// Arweave allows, followed by each of `auto_tags` whose name isn't already there. Names are
+/// compared ignoring ascii case, so a caller's `content-type` replaces the detected
+/// `Content-Type`.
+pub fn merge_tags(tags: Vec<Tag<Base64>>, auto_tags: Vec<Tag<Base64>>) -> Vec<Tag<Base64>> {
+    let mut merged = tags;
+    for auto_tag in auto_tags {
+        if !merged
+            .iter()
+            .any(|tag| tag.name.0.eq_ignore_ascii_case(&auto_tag.name.0))
+        {
+            merged.push(auto_tag);
+        }
+    }
+    merged
+}
+
+/// `App-Name` and `App-Version` tags naming this crate, added by [`TxBuilder`] unless turned
+/// off with [`TxBuilder::app_tags`].
+///
+/// [`TxBuilder`]: crate::transaction::builder::TxBuilder
+/// [`TxBuilder::app_tags`]: crate::transaction::builder::TxBuilder::app_tags
+pub fn app_tags() -> Result<Vec<Tag<Base64>>, Error> {
+    Ok(vec![
+        Tag::<Base64>::from_utf8_strs("App-Name", "arweave-rs")?,
+        Tag::<Base64>::from_utf8_strs("App-Version", VERSION)?,
+    ])
+}
+
+pub trait Generator: Send + Sync {
+    #[allow(clippy::too_many_arguments)]
+    fn new_transaction(
+        &self,
+        crypto: &Provider,
+        target: Base64,
+        data: Vec<u8>,
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error>;
+
+    /// Transaction that only uploads `data`: no target and nothing transferred. Builds with
+    /// [`Generator::new_transaction`] unless overridden.
+    fn new_data_transaction(
+        &self,
+        crypto: &Provider,
+        data: Vec<u8>,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        self.new_transaction(
+            crypto,
+            Base64::empty(),
+            data,
+            0,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+
+    /// Same as [`Generator::new_transaction`], but borrows `data` and leaves the transaction's
+    /// `data` empty. Builds with [`Tx::new_with_data_ref`] unless overridden.
+    #[allow(clippy::too_many_arguments)]
+    fn new_transaction_with_data_ref(
+        &self,
+        crypto: &Provider,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        Tx::new_with_data_ref(
+            crypto,
+            target,
+            data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+
+    /// Unsigned ANS-104 data item for a bundle. Unlike a transaction's `last_tx`, `anchor` is
+    /// chosen by the caller, and an empty `anchor` is replaced with 32 bytes from
+    /// [`Base64::random`] so items with the same contents can be told apart. `target` may be
+    /// empty. Builds with [`DataItem::new`] unless overridden.
+    fn new_data_item(
+        &self,
+        target: Base64,
+        anchor: Base64,
+        tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+    ) -> Result<DataItem, Error> {
+        let anchor = match anchor.is_empty() {
+            true => Base64::random(32),
+            false => anchor,
+        };
+        DataItem::new(target, anchor, tags, data)
+    }
+
+    /// Anchor to use instead of fetching one from the gateway, if any.
+    fn fixed_anchor(&self) -> Option<Base64> {
+        None
+    }
+}
+
+/// Default generator, building transactions with [`Tx::new`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TxGenerator;
+
+impl Generator for TxGenerator {
+    fn new_transaction(
+        &self,
+        crypto: &Provider,
+        target: Base64,
+        data: Vec<u8>,
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        Tx::new(
+            crypto,
+            target,
+            data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
+}
+
+/// Generator for tests that builds transactions with [`Tx::new`] but with a caller supplied
+/// anchor, so no anchor is fetched from the gateway, and a caller supplied id. The id is
+/// replaced when the transaction is signed.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Clone, Debug, Default)]
+pub struct MockGenerator {
+    pub anchor: Base64,
+    pub id: Base64,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Generator for MockGenerator {
+    fn new_transaction(
+        &self,
+        crypto: &Provider,
+        target: Base64,
+        data: Vec<u8>,
+        quantity: u128,
+        fee: u64,
+        _last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        let mut transaction = Tx::new(
+            crypto,
+            target,
+            data,
+            quantity,
+            fee,
+            self.anchor.clone(),
+            other_tags,
+            auto_content_tag,
+        )?;
+        transaction.id = self.id.clone();
+        Ok(transaction)
+    }
+
+    fn new_transaction_with_data_ref(
+        &self,
+        crypto: &Provider,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        _last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Tx, Error> {
+        let mut transaction = Tx::new_with_data_ref(
+            crypto,
+            target,
+            data,
+            quantity,
+            fee,
+            self.anchor.clone(),
+            other_tags,
+            auto_content_tag,
+        )?;
+        transaction.id = self.id.clone();
+        Ok(transaction)
+    }
+
+    /// Keeps `anchor` as given, so an empty one stays empty.
+    fn new_data_item(
+        &self,
+        target: Base64,
+        anchor: Base64,
+        tags: Vec<Tag<Base64>>,
+        data: Vec<u8>,
+    ) -> Result<DataItem, Error> {
+        DataItem::new(target, anchor, tags, data)
+    }
+
+    fn fixed_anchor(&self) -> Option<Base64> {
+        Some(self.anchor.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use httpmock::MockServer;
+    use pretend::Url;
+    use tokio_test::block_on;
+
+    use crate::{
+        crypto::{base64::Base64, Provider},
+        currency::Winston,
+        transaction::tags::{FromUtf8Strs, Tag},
+        Arweave,
+    };
+
+    use super::{merge_tags, Generator, MockGenerator, TxGenerator};
+    use crate::{bundle::DataItem, error::Error, transaction::Tx};
+
+    #[test]
+    fn test_merge_tags() {
+        let tag = |name: &str, value: &str| Tag::<Base64>::from_utf8_strs(name, value).unwrap();
+        let merged = merge_tags(
+            vec![
+                tag("App-Name", "mine"),
+                tag("content-type", "text/html"),
+                tag("App-Name", "mine"),
+            ],
+            vec![
+                tag("Content-Type", "text/plain"),
+                tag("User-Agent", "arweave-rs"),
+            ],
+        );
+        assert_eq!(
+            merged,
+            vec![
+                tag("App-Name", "mine"),
+                tag("content-type", "text/html"),
+                tag("App-Name", "mine"),
+                tag("User-Agent", "arweave-rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mock_generator() {
+        // Nothing is mocked, so any request would fail.
+        let server = MockServer::start();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet.json").unwrap(),
+            Url::parse(&server.url("/")).unwrap(),
+        )
+        .unwrap()
+        .with_generator(Box::new(MockGenerator {
+            anchor: Base64(vec![1; 48]),
+            id: Base64(vec![2; 32]),
+        }));
+
+        let tx = block_on(arweave.create_transaction(
+            Base64::empty(),
+            vec![],
+            b"test".to_vec(),
+            Winston(0),
+            Winston(1000),
+            false,
+        ))
+        .unwrap();
+
+        assert_eq!(tx.id, Base64(vec![2; 32]));
+        assert_eq!(tx.last_tx, Base64(vec![1; 48]));
+        assert_eq!(tx.data, Base64(b"test".to_vec()));
+        assert_eq!(tx.reward, 1000);
+    }
+
+    /// Builds every data item with the same anchor.
+    struct FixedItemAnchor;
+
+    impl Generator for FixedItemAnchor {
+        fn new_transaction(
+            &self,
+            crypto: &Provider,
+            target: Base64,
+            data: Vec<u8>,
+            quantity: u128,
+            fee: u64,
+            last_tx: Base64,
+            other_tags: Vec<Tag<Base64>>,
+            auto_content_tag: bool,
+        ) -> Result<Tx, Error> {
+            TxGenerator.new_transaction(
+                crypto,
+                target,
+                data,
+                quantity,
+                fee,
+                last_tx,
+                other_tags,
+                auto_content_tag,
+            )
+        }
+
+        fn new_data_item(
+            &self,
+            target: Base64,
+            _anchor: Base64,
+            tags: Vec<Tag<Base64>>,
+            data: Vec<u8>,
+        ) -> Result<DataItem, Error> {
+            DataItem::new(target, Base64(vec![7; 32]), tags, data)
+        }
+    }
+
+    #[test]
+    fn test_arweave_uses_generator_for_data() {
+        // Nothing is mocked, so any request would fail.
+        let server = MockServer::start();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet_4096.json").unwrap(),
+            Url::parse(&server.url("/")).unwrap(),
+        )
+        .unwrap();
+
+        let item = arweave
+            .with_generator(Box::new(FixedItemAnchor))
+            .create_data_item(Base64::empty(), Base64::empty(), vec![], b"item".to_vec())
+            .unwrap();
+        assert_eq!(item.anchor, Base64(vec![7; 32]));
+
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from_str("res/test_wallet_4096.json").unwrap(),
+            Url::parse(&server.url("/")).unwrap(),
+        )
+        .unwrap()
+        .with_generator(Box::new(MockGenerator {
+            anchor: Base64(vec![1; 48]),
+            id: Base64(vec![2; 32]),
+        }));
+        let item = arweave.sign_data_item(item).unwrap();
+        item.verify().unwrap();
+        let tx =
+            block_on(arweave.create_bundle_transaction(vec![item], vec![], Winston(1000))).unwrap();
+        assert_eq!(tx.id, Base64(vec![2; 32]));
+        assert_eq!(tx.last_tx, Base64(vec![1; 48]));
+        assert!(tx.target.is_empty());
+    }
+
+    #[test]
+    fn test_data_transaction_and_item() {
+        let crypto =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet.json").unwrap())
+                .unwrap();
+        let tx = TxGenerator
+            .new_data_transaction(
+                &crypto,
+                b"test".to_vec(),
+                1000,
+                Base64(vec![1; 32]),
+                vec![],
+                false,
+            )
+            .unwrap();
+        assert!(tx.target.is_empty());
+        assert_eq!(tx.quantity, 0u128.into());
+        assert_eq!(tx.reward, 1000);
+        assert_eq!(tx.last_tx, Base64(vec![1; 32]));
+
+        let generator = MockGenerator {
+            anchor: Base64(vec![1; 48]),
+            id: Base64(vec![2; 32]),
+        };
+        let tx = generator
+            .new_data_transaction(
+                &crypto,
+                b"test".to_vec(),
+                1000,
+                Base64::empty(),
+                vec![],
+                false,
+            )
+            .unwrap();
+        assert_eq!(tx.last_tx, Base64(vec![1; 48]));
+        assert_eq!(tx.id, Base64(vec![2; 32]));
+
+        let item = generator
+            .new_data_item(
+                Base64::empty(),
+                Base64(vec![3; 32]),
+                vec![],
+                b"item".to_vec(),
+            )
+            .unwrap();
+        assert!(item.target.is_empty());
+        assert_eq!(item.anchor, Base64(vec![3; 32]));
+        assert!(matches!(
+            generator.new_data_item(Base64::empty(), Base64(vec![3; 48]), vec![], vec![]),
+            Err(Error::InvalidBundle(reason)) if reason == "anchor must be empty or 32 bytes"
+        ));
+        let item = generator
+            .new_data_item(Base64::empty(), Base64::empty(), vec![], vec![])
+            .unwrap();
+        assert!(item.anchor.is_empty());
+
+        let first = TxGenerator
+            .new_data_item(Base64::empty(), Base64::empty(), vec![], b"item".to_vec())
+            .unwrap();
+        let second = TxGenerator
+            .new_data_item(Base64::empty(), Base64::empty(), vec![], b"item".to_vec())
+            .unwrap();
+        assert_eq!(first.anchor.0.len(), 32);
+        assert_ne!(first.anchor, second.anchor);
+    }
+}
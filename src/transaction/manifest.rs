@@ -0,0 +1,148 @@
+//! [Path manifests](https://github.com/ArweaveTeam/arweave/wiki/Path-Manifests), which map
+//! paths to transaction ids so that gateways can serve a set of uploaded files as a site.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{crypto::base64::Base64, error::Error};
+
+/// Content type gateways use to recognise a path manifest.
+pub const MANIFEST_CONTENT_TYPE: &str = "application/x.arweave-manifest+json";
+
+const MANIFEST_TYPE: &str = "arweave/paths";
+const MANIFEST_VERSIONS: [&str; 2] = ["0.1.0", "0.2.0"];
+const ID_LENGTH: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub manifest: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<ManifestIndex>,
+    pub paths: BTreeMap<String, ManifestPath>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestIndex {
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestPath {
+    pub id: Base64,
+}
+
+impl Manifest {
+    /// Manifest mapping each of `paths` to its transaction id, served at `index` when the
+    /// manifest itself is requested.
+    pub fn new(paths: BTreeMap<String, Base64>, index: Option<String>) -> Result<Self, Error> {
+        let manifest = Self {
+            manifest: MANIFEST_TYPE.to_string(),
+            version: MANIFEST_VERSIONS[0].to_string(),
+            index: index.map(|path| ManifestIndex { path }),
+            paths: paths
+                .into_iter()
+                .map(|(path, id)| (path, ManifestPath { id }))
+                .collect(),
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Parses manifest JSON, returning [`Error::InvalidManifest`] unless it is an
+    /// `arweave/paths` manifest whose paths all point at transaction ids and whose index, if
+    /// any, is one of its paths.
+    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
+        let manifest: Manifest =
+            serde_json::from_slice(data).map_err(|e| Error::InvalidManifest(e.to_string()))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.manifest != MANIFEST_TYPE {
+            return Err(Error::InvalidManifest(format!(
+                "manifest must be \"{}\", got \"{}\"",
+                MANIFEST_TYPE, self.manifest
+            )));
+        }
+        if !MANIFEST_VERSIONS.contains(&self.version.as_str()) {
+            return Err(Error::InvalidManifest(format!(
+                "unsupported version \"{}\"",
+                self.version
+            )));
+        }
+        if let Some((path, _)) = self
+            .paths
+            .iter()
+            .find(|(_, path)| path.id.0.len() != ID_LENGTH)
+        {
+            return Err(Error::InvalidManifest(format!(
+                "path \"{}\" doesn't point at a transaction id",
+                path
+            )));
+        }
+        if let Some(index) = &self.index {
+            if !self.paths.contains_key(&index.path) {
+                return Err(Error::InvalidManifest(format!(
+                    "index path \"{}\" is not in paths",
+                    index.path
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{crypto::base64::Base64, error::Error};
+
+    use super::Manifest;
+
+    #[test]
+    fn test_manifest_validation() {
+        let id = Base64(vec![1; 32]).to_string();
+        let valid = json!({
+            "manifest": "arweave/paths",
+            "version": "0.1.0",
+            "index": { "path": "index.html" },
+            "paths": {
+                "index.html": { "id": id },
+                "css/style.css": { "id": id },
+            },
+        });
+        let manifest = Manifest::from_slice(valid.to_string().as_bytes()).unwrap();
+        assert_eq!(manifest.paths.len(), 2);
+        assert_eq!(serde_json::to_value(&manifest).unwrap(), valid);
+
+        let mut wrong_type = valid.clone();
+        wrong_type["manifest"] = json!("arweave/files");
+        let mut missing_index = valid.clone();
+        missing_index["index"]["path"] = json!("home.html");
+        let mut short_id = valid.clone();
+        short_id["paths"]["index.html"]["id"] = json!("AQEB");
+        let mut no_paths = valid;
+        no_paths.as_object_mut().unwrap().remove("paths");
+
+        for invalid in [
+            wrong_type,
+            missing_index,
+            short_id,
+            no_paths,
+            json!(["index.html"]),
+        ] {
+            assert!(matches!(
+                Manifest::from_slice(invalid.to_string().as_bytes()),
+                Err(Error::InvalidManifest(_))
+            ));
+        }
+        assert!(matches!(
+            Manifest::from_slice(b"not json"),
+            Err(Error::InvalidManifest(_))
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+//! Data root and chunk layout of transaction data, as the node expects them for chunked
+//! uploads. Built on the merkle tree in [`crate::crypto::merkle`].
+
+use std::io::Read;
+
+use crate::{
+    crypto::{
+        base64::Base64,
+        merkle::{self, generate_leaves, generate_leaves_from_reader, resolve_proofs, Node, Proof},
+    },
+    error::Error,
+};
+
+/// One chunk of transaction data with the proof that places it under the data root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub data_hash: Base64,
+    pub min_byte_range: usize,
+    pub max_byte_range: usize,
+    /// Proof posted as the chunk's `data_path` to the `chunk/` endpoint.
+    pub data_path: Base64,
+}
+
+/// Data root of `data` with its chunks in order. Chunk boundaries follow
+/// [`merkle::chunk_ranges`], including the rebalancing of a short last chunk. Empty data has an
+/// empty data root and no chunks.
+pub fn generate_data_root(data: &[u8]) -> Result<(Base64, Vec<Chunk>), Error> {
+    let (data_root, leaves, proofs) = merkle_tree(data)?;
+    let chunks = leaves
+        .into_iter()
+        .zip(proofs)
+        .map(|(leaf, proof)| Chunk {
+            data_hash: Base64(leaf.data_hash.map(|h| h.to_vec()).unwrap_or_default()),
+            min_byte_range: leaf.min_byte_range,
+            max_byte_range: leaf.max_byte_range,
+            data_path: Base64(proof.proof),
+        })
+        .collect();
+    Ok((data_root, chunks))
+}
+
+/// Data root, leaves and leaf proofs of `data`, leaving out the trailing empty chunk that data
+/// ending on a chunk boundary gets.
+pub(crate) fn merkle_tree(data: &[u8]) -> Result<(Base64, Vec<Node>, Vec<Proof>), Error> {
+    if data.is_empty() {
+        return Ok((Base64::empty(), vec![], vec![]));
+    }
+
+    tree_from_leaves(generate_leaves(data)?)
+}
+
+/// Same as [`merkle_tree`] for `data_size` bytes read from `reader`, so that the data never
+/// has to be held in memory at once.
+pub(crate) fn merkle_tree_from_reader<R: Read>(
+    reader: &mut R,
+    data_size: usize,
+) -> Result<(Base64, Vec<Node>, Vec<Proof>), Error> {
+    if data_size == 0 {
+        return Ok((Base64::empty(), vec![], vec![]));
+    }
+
+    tree_from_leaves(generate_leaves_from_reader(reader, data_size)?)
+}
+
+fn tree_from_leaves(mut leaves: Vec<Node>) -> Result<(Base64, Vec<Node>, Vec<Proof>), Error> {
+    let root = merkle::generate_data_root(leaves.clone())?;
+    let data_root = Base64(root.id.to_vec());
+    let mut proofs = resolve_proofs(root, None)?;
+
+    if let Some(last) = leaves.last() {
+        if last.max_byte_range == last.min_byte_range {
+            leaves.pop();
+            proofs.pop();
+        }
+    }
+    Ok((data_root, leaves, proofs))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use crate::{
+        crypto::{
+            base64::Base64,
+            hash::sha256,
+            merkle::{MAX_CHUNK_SIZE, MIN_CHUNK_SIZE},
+        },
+        error::Error,
+    };
+
+    use super::generate_data_root;
+
+    #[test]
+    fn test_generate_data_root() -> Result<(), Error> {
+        // Root as calculated by arweave-js.
+        let data = fs::read("res/rebar3").unwrap();
+        let (data_root, chunks) = generate_data_root(&data)?;
+        assert_eq!(
+            data_root,
+            Base64::from_str("t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw").unwrap()
+        );
+        assert_eq!(chunks.first().unwrap().min_byte_range, 0);
+        assert_eq!(chunks.last().unwrap().max_byte_range, data.len());
+        for (chunk, next) in chunks.iter().zip(chunks.iter().skip(1)) {
+            assert_eq!(chunk.max_byte_range, next.min_byte_range);
+        }
+        for chunk in &chunks {
+            let bytes = &data[chunk.min_byte_range..chunk.max_byte_range];
+            assert_eq!(chunk.data_hash.0, sha256(bytes).to_vec());
+            assert!(!chunk.data_path.is_empty());
+        }
+
+        let data = vec![0; MAX_CHUNK_SIZE + 1];
+        let (data_root, chunks) = generate_data_root(&data)?;
+        assert_eq!(
+            data_root,
+            Base64::from_str("br1Vtl3TS_NGWdHmYqBh3-MxrlckoluHCZGmUZk-dJc").unwrap()
+        );
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[1].max_byte_range - chunks[1].min_byte_range >= MIN_CHUNK_SIZE);
+
+        let (data_root, chunks) = generate_data_root(&vec![1; 2 * MAX_CHUNK_SIZE])?;
+        assert!(!data_root.is_empty());
+        assert_eq!(chunks.len(), 2);
+
+        assert_eq!(generate_data_root(&[])?, (Base64::empty(), vec![]));
+        Ok(())
+    }
+}
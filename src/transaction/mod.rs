@@ -1,11 +1,14 @@
-use serde::Deserialize;
+use std::io::{Read, Seek, SeekFrom};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_aux::prelude::deserialize_number_from_string;
 
 use crate::{
-    consts::VERSION,
+    consts::{MAX_TX_DATA, VERSION},
     crypto::{base64::Base64, Provider},
     crypto::{
-        hash::{DeepHashItem, ToItems},
-        merkle::{generate_data_root, generate_leaves, resolve_proofs, Node, Proof},
+        hash::{deep_hash, sha256, DeepHashItem, ToItems},
+        merkle::{chunk_ranges, Node, Proof, MAX_CHUNK_SIZE},
     },
     currency::Currency,
     error::Error,
@@ -13,16 +16,109 @@ use crate::{
     types::Chunk,
 };
 
-use self::tags::FromUtf8Strs;
+use self::{generator::merge_tags, tags::FromUtf8Strs};
 
+pub mod builder;
 pub mod client;
+pub mod generator;
+pub mod manifest;
+pub mod merkle;
 pub mod parser;
 pub mod tags;
 
+/// Transaction format, serialized as its number. Format 2 moved the data out of the signature,
+/// committing to its data root instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TxFormat {
+    V1,
+    #[default]
+    V2,
+}
+
+impl From<TxFormat> for u8 {
+    fn from(format: TxFormat) -> Self {
+        match format {
+            TxFormat::V1 => 1,
+            TxFormat::V2 => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for TxFormat {
+    type Error = Error;
+
+    fn try_from(format: u8) -> Result<Self, Self::Error> {
+        match format {
+            1 => Ok(TxFormat::V1),
+            2 => Ok(TxFormat::V2),
+            format => Err(Error::InvalidTxFormat(format)),
+        }
+    }
+}
+
+impl std::fmt::Display for TxFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+impl Serialize for TxFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for TxFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let format = u8::deserialize(deserializer)?;
+        TxFormat::try_from(format).map_err(de::Error::custom)
+    }
+}
+
+/// Remembers, in debug builds, that a transaction was signed by this crate but not posted yet,
+/// so that dropping it logs a warning about a possibly lost upload. Empty in release builds.
+#[derive(Debug, Default)]
+pub(crate) struct PostedFlag {
+    #[cfg(debug_assertions)]
+    unposted_id: std::sync::Mutex<Option<String>>,
+}
+
+impl PostedFlag {
+    pub(crate) fn set_signed(&self, _id: &Base64) {
+        #[cfg(debug_assertions)]
+        {
+            *self.unposted_id.lock().unwrap() = Some(_id.to_string());
+        }
+    }
+
+    pub(crate) fn set_posted(&self) {
+        #[cfg(debug_assertions)]
+        {
+            *self.unposted_id.lock().unwrap() = None;
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for PostedFlag {
+    fn drop(&mut self) {
+        if let Some(id) = self.unposted_id.get_mut().ok().and_then(|id| id.take()) {
+            tracing::warn!(%id, "signed transaction dropped without being posted");
+        }
+    }
+}
+
+impl PartialEq for PostedFlag {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
 #[derive(Deserialize, Debug, Default, PartialEq)]
 pub struct Tx {
     /* Fields required for signing */
-    pub format: u8,
+    pub format: TxFormat,
     pub id: Base64,
     pub last_tx: Base64,
     pub owner: Base64,
@@ -31,21 +127,25 @@ pub struct Tx {
     pub quantity: Currency,
     pub data_root: Base64,
     pub data: Base64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
     pub data_size: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
     pub reward: u64,
     pub signature: Base64,
     #[serde(skip)]
     pub chunks: Vec<Node>,
     #[serde(skip)]
     pub proofs: Vec<Proof>,
+    #[serde(skip)]
+    pub(crate) posted: PostedFlag,
 }
 
 impl<'a> ToItems<'a, Tx> for Tx {
     fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
         match &self.format {
-            1 => {
-                let quantity = Base64::from_utf8_str(&self.quantity.to_string()).unwrap();
-                let reward = Base64::from_utf8_str(&self.reward.to_string()).unwrap();
+            TxFormat::V1 => {
+                let quantity = Base64::from_utf8_str(&self.quantity.to_string())?;
+                let reward = Base64::from_utf8_str(&self.reward.to_string())?;
                 let mut children: Vec<DeepHashItem> = vec![
                     &self.owner,
                     &self.target,
@@ -61,7 +161,7 @@ impl<'a> ToItems<'a, Tx> for Tx {
 
                 Ok(DeepHashItem::from_children(children))
             }
-            2 => {
+            TxFormat::V2 => {
                 let mut children: Vec<DeepHashItem> = vec![
                     self.format.to_string().as_bytes(),
                     &self.owner.0,
@@ -73,7 +173,7 @@ impl<'a> ToItems<'a, Tx> for Tx {
                 .into_iter()
                 .map(DeepHashItem::from_item)
                 .collect();
-                children.push(self.tags.to_deep_hash_item().unwrap());
+                children.push(self.tags.to_deep_hash_item()?);
                 children.push(DeepHashItem::from_item(
                     self.data_size.to_string().as_bytes(),
                 ));
@@ -81,7 +181,6 @@ impl<'a> ToItems<'a, Tx> for Tx {
 
                 Ok(DeepHashItem::from_children(children))
             }
-            _ => unreachable!(),
         }
     }
 }
@@ -91,45 +190,47 @@ impl Tx {
         Tag::<Base64>::from_utf8_strs("User-Agent", &format!("arweave-rs/{}", VERSION)).unwrap()
     }
 
-    fn generate_merkle(data: Vec<u8>) -> Result<Tx, Error> {
-        if data.is_empty() {
-            let empty = Base64(vec![]);
-            Ok(Tx {
-                format: 2,
-                data_size: 0,
-                data: empty.clone(),
-                data_root: empty,
-                chunks: vec![],
-                proofs: vec![],
-                ..Default::default()
-            })
-        } else {
-            let mut chunks = generate_leaves(data.clone()).unwrap();
-            let root = generate_data_root(chunks.clone()).unwrap();
-            let data_root = Base64(root.id.into_iter().collect());
-            let mut proofs = resolve_proofs(root, None).unwrap();
-
-            // Discard the last chunk & proof if it's zero length.
-            let last_chunk = chunks.last().unwrap();
-            if last_chunk.max_byte_range == last_chunk.min_byte_range {
-                chunks.pop();
-                proofs.pop();
-            }
+    /// Chunks and merkle tree for `data`. The returned transaction's `data` is left empty.
+    fn generate_merkle(data: &[u8]) -> Result<Tx, Error> {
+        let (data_root, chunks, proofs) = merkle::merkle_tree(data)?;
+        Ok(Tx {
+            format: TxFormat::V2,
+            data_size: data.len() as u64,
+            data_root,
+            chunks,
+            proofs,
+            ..Default::default()
+        })
+    }
+}
 
-            Ok(Tx {
-                format: 2,
-                data_size: data.len() as u64,
-                data: Base64(data),
-                data_root,
-                chunks,
-                proofs,
-                ..Default::default()
-            })
-        }
+/// MIME type of `data` from its [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types),
+/// e.g. PNG, JPEG, PDF or gzip. Data without a signature is `application/json` if it parses as
+/// a JSON object or array, `text/plain` if it is UTF-8 and `application/octet-stream` otherwise.
+pub fn detect_content_type(data: &[u8]) -> &'static str {
+    if let Some(kind) = infer::get(data) {
+        return kind.mime_type();
+    }
+    let text = match std::str::from_utf8(data) {
+        Ok(text) if !text.trim().is_empty() => text.trim(),
+        _ => return "application/octet-stream",
+    };
+    if (text.starts_with('{') || text.starts_with('['))
+        && serde_json::from_str::<de::IgnoredAny>(text).is_ok()
+    {
+        "application/json"
+    } else if text.chars().all(|c| !c.is_control() || c.is_whitespace()) {
+        "text/plain"
+    } else {
+        "application/octet-stream"
     }
 }
 
 impl Tx {
+    /// Builds an unsigned transaction. `owner` is set here from `crypto`'s public modulus rather
+    /// than when signing, so an unsigned transaction already names its owner, e.g. for signing
+    /// or verifying on an air-gapped machine.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         crypto: &Provider,
         target: Base64,
@@ -140,39 +241,134 @@ impl Tx {
         other_tags: Vec<Tag<Base64>>,
         auto_content_tag: bool,
     ) -> Result<Self, Error> {
-        if quantity.lt(&0) {
-            return Err(Error::InvalidValueForTx);
-        }
+        let mut transaction = Tx::new_with_data_ref(
+            crypto,
+            target,
+            &data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )?;
+        transaction.data = Base64(data);
+        Ok(transaction)
+    }
+
+    /// Builds an unsigned transaction the same way as [`Tx::new`] without taking or copying
+    /// `data`: the merkle tree is computed from the slice and the transaction's `data` is left
+    /// empty. The data then has to be posted as chunks, e.g. with
+    /// [`crate::Arweave::post_transaction_with_shared_data`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_data_ref(
+        crypto: &Provider,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Self, Error> {
+        let transaction = Tx::generate_merkle(data)?;
+        transaction.with_header(
+            crypto,
+            target,
+            data,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
 
-        let mut transaction = Tx::generate_merkle(data).unwrap();
-        transaction.owner = crypto.keypair_modulus();
+    /// Builds an unsigned transaction the same way as [`Tx::new_with_data_ref`] for the
+    /// first `data_size` bytes of `reader`, reading one chunk at a time so the data is never
+    /// held in memory at once. The content type is detected from the first
+    /// chunk. The data then has to be posted as chunks, e.g. with
+    /// [`crate::Arweave::post_transaction_from_reader`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_from_reader<R: Read + Seek>(
+        crypto: &Provider,
+        target: Base64,
+        reader: &mut R,
+        data_size: u64,
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Self, Error> {
+        let io_error = |e: std::io::Error| Error::IoError(e.to_string());
+        let mut head = vec![0; (data_size as usize).min(MAX_CHUNK_SIZE)];
+        reader.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        reader.read_exact(&mut head).map_err(io_error)?;
+        reader.seek(SeekFrom::Start(0)).map_err(io_error)?;
 
-        let mut tags = vec![Tx::base_tag()];
+        let (data_root, chunks, proofs) =
+            merkle::merkle_tree_from_reader(reader, data_size as usize)?;
+        let transaction = Tx {
+            format: TxFormat::V2,
+            data_size,
+            data_root,
+            chunks,
+            proofs,
+            ..Default::default()
+        };
+        transaction.with_header(
+            crypto,
+            target,
+            &head,
+            quantity,
+            fee,
+            last_tx,
+            other_tags,
+            auto_content_tag,
+        )
+    }
 
-        // Get content type from [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
-        // and include additional tags if any.
-        if auto_content_tag {
-            let content_type = if let Some(kind) = infer::get(&transaction.data.0) {
-                kind.mime_type()
-            } else {
-                "application/octet-stream"
-            };
+    /// Fills in the fields of a transaction whose data fields are already set. `data` is what
+    /// the content type is detected from.
+    #[allow(clippy::too_many_arguments)]
+    fn with_header(
+        mut self,
+        crypto: &Provider,
+        target: Base64,
+        data: &[u8],
+        quantity: u128,
+        fee: u64,
+        last_tx: Base64,
+        other_tags: Vec<Tag<Base64>>,
+        auto_content_tag: bool,
+    ) -> Result<Self, Error> {
+        self.owner = crypto.keypair_modulus();
 
-            tags.push(Tag::<Base64>::from_utf8_strs("Content-Type", content_type)?)
+        let mut auto_tags = vec![Tx::base_tag()];
+        if auto_content_tag {
+            auto_tags.push(Tag::<Base64>::from_utf8_strs(
+                "Content-Type",
+                detect_content_type(data),
+            )?)
         }
-
-        // Add other tags if provided.
-        tags.extend(other_tags);
-        transaction.tags = tags;
+        self.tags = merge_tags(other_tags, auto_tags);
 
         // Fetch and set last_tx if not provided (primarily for testing).
-        transaction.last_tx = last_tx;
+        self.last_tx = last_tx;
 
-        transaction.reward = fee;
-        transaction.quantity = Currency::from(quantity);
-        transaction.target = target;
+        self.reward = fee;
+        self.quantity = Currency::from(quantity);
+        self.target = target;
 
-        Ok(transaction)
+        Ok(self)
+    }
+
+    /// Hash of the data the signature is computed over. Unlike `id`, which is the hash of the
+    /// signature and so only exists once the transaction is signed, this can be computed before
+    /// signing and stays the same for the same content. It is not the on-chain id.
+    pub fn content_digest(&self) -> Result<Base64, Error> {
+        let signature_data = deep_hash(self.to_deep_hash_item()?);
+        Ok(Base64(sha256(&signature_data).to_vec()))
     }
 
     pub fn clone_with_no_data(&self) -> Result<Self, Error> {
@@ -191,19 +387,453 @@ impl Tx {
             signature: self.signature.clone(),
             chunks: Vec::new(),
             proofs: Vec::new(),
+            posted: PostedFlag::default(),
         })
     }
 
+    /// Value of the first tag named `name`, decoded as UTF-8. Names are compared ignoring ascii
+    /// case, as when merging tags, so `content-type` finds `Content-Type`. `None` if there is no
+    /// such tag or its value isn't valid UTF-8.
+    pub fn get_tag(&self, name: &str) -> Option<String> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name.0.eq_ignore_ascii_case(name.as_bytes()))
+            .and_then(|tag| tag.value.to_utf8_string().ok())
+    }
+
+    /// Names and values of the tags decoded as UTF-8, in order, skipping tags that aren't valid
+    /// UTF-8.
+    pub fn tags_as_strings(&self) -> Vec<(String, String)> {
+        self.tags
+            .iter()
+            .filter_map(|tag| {
+                Some((
+                    tag.name.to_utf8_string().ok()?,
+                    tag.value.to_utf8_string().ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// The bytes the signature is computed over, the 48 byte deep hash of the transaction, the
+    /// same as arweave-js's `getSignatureData`. Compare them across implementations when a
+    /// signature doesn't verify.
+    pub fn signature_data(&self) -> Result<Vec<u8>, Error> {
+        Ok(deep_hash(self.to_deep_hash_item()?).to_vec())
+    }
+
+    /// Whether the data is too large to send inline to the `tx/` endpoint, so that it gets
+    /// posted to the `chunk/` endpoint instead.
+    pub fn will_chunk(&self) -> bool {
+        self.data_size > MAX_TX_DATA
+    }
+
+    /// Number of chunks `data_size` bytes of data are split into, whether or not they have been
+    /// generated for this transaction.
+    pub fn chunk_count(&self) -> u64 {
+        chunk_ranges(self.data_size as usize).map_or(0, |ranges| ranges.len() as u64)
+    }
+
+    /// Size in bytes of the JSON body sent to the `tx/` endpoint. Binary fields are base64url
+    /// encoded, so they take up roughly 4/3 of their raw size.
+    pub fn json_post_size(&self) -> u64 {
+        fn b64_len(bytes: &Base64) -> u64 {
+            (bytes.0.len() as u64 * 4).div_ceil(3)
+        }
+        // `"name":"value"` with the quotes, colon and a separating comma.
+        fn string_field(name: &str, value_len: u64) -> u64 {
+            name.len() as u64 + value_len + 6
+        }
+
+        // Each tag object adds braces and a separating comma, less the comma after `value`.
+        let tags: u64 = self
+            .tags
+            .iter()
+            .map(|t| {
+                string_field("name", b64_len(&t.name))
+                    + string_field("value", b64_len(&t.value))
+                    + 2
+            })
+            .sum();
+        let fields = [
+            ("id", b64_len(&self.id)),
+            ("last_tx", b64_len(&self.last_tx)),
+            ("owner", b64_len(&self.owner)),
+            ("target", b64_len(&self.target)),
+            ("quantity", self.quantity.to_string().len() as u64),
+            ("data", b64_len(&self.data)),
+            ("data_size", self.data_size.to_string().len() as u64),
+            ("data_root", b64_len(&self.data_root)),
+            ("reward", self.reward.to_string().len() as u64),
+            ("signature", b64_len(&self.signature)),
+        ];
+        let strings: u64 = fields.iter().map(|(n, l)| string_field(n, *l)).sum();
+
+        // `"format":2,` and `"tags":[],` plus the braces, less the trailing comma.
+        let format = "format".len() as u64 + self.format.to_string().len() as u64 + 4;
+        let tags_field = "tags".len() as u64 + 6 + tags.saturating_sub(1);
+        format + tags_field + strings + 1
+    }
+
+    /// Link to the transaction's data on the gateway at `base_url`, i.e. `{base_url}/{id}`.
+    pub fn gateway_url(&self, base_url: &url::Url) -> Result<url::Url, Error> {
+        if self.id.0.is_empty() {
+            return Err(Error::UnsignedTransaction);
+        }
+        let mut base_url = base_url.clone();
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        base_url
+            .join(&self.id.to_string())
+            .map_err(|e| Error::TransactionInfoError(e.to_string()))
+    }
+
     pub fn get_chunk(&self, idx: usize) -> Result<Chunk, Error> {
+        self.get_chunk_from(idx, &self.data.0)
+    }
+
+    /// Chunk `idx` of the data in `reader`, read from the chunk's offset from the start of
+    /// `reader`.
+    pub fn get_chunk_from_reader<R: Read + Seek>(
+        &self,
+        idx: usize,
+        reader: &mut R,
+    ) -> Result<Chunk, Error> {
+        let (node, proof) = self
+            .chunks
+            .get(idx)
+            .zip(self.proofs.get(idx))
+            .ok_or(Error::SliceError)?;
+        let io_error = |e: std::io::Error| Error::IoError(e.to_string());
+        let mut chunk = vec![0; node.max_byte_range - node.min_byte_range];
+        reader
+            .seek(SeekFrom::Start(node.min_byte_range as u64))
+            .map_err(io_error)?;
+        reader.read_exact(&mut chunk).map_err(io_error)?;
+        Ok(Chunk {
+            data_root: self.data_root.clone(),
+            data_size: self.data_size,
+            data_path: Base64(proof.proof.clone()),
+            offset: proof.offset,
+            chunk: Base64(chunk),
+        })
+    }
+
+    /// Chunk `idx` of `data`, for transactions whose data is held outside of `data`.
+    pub fn get_chunk_from(&self, idx: usize, data: &[u8]) -> Result<Chunk, Error> {
+        let (node, proof) = self
+            .chunks
+            .get(idx)
+            .zip(self.proofs.get(idx))
+            .ok_or(Error::SliceError)?;
+        let chunk = data
+            .get(node.min_byte_range..node.max_byte_range)
+            .ok_or(Error::SliceError)?;
         Ok(Chunk {
             data_root: self.data_root.clone(),
             data_size: self.data_size,
-            data_path: Base64(self.proofs[idx].proof.clone()),
-            offset: self.proofs[idx].offset,
-            chunk: Base64(
-                self.data.0[self.chunks[idx].min_byte_range..self.chunks[idx].max_byte_range]
-                    .to_vec(),
-            ),
+            data_path: Base64(proof.proof.clone()),
+            offset: proof.offset,
+            chunk: Base64(chunk.to_vec()),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, str::FromStr};
+
+    use proptest::prelude::*;
+
+    use crate::{
+        consts::{ARWEAVE_BASE_URL, MAX_TX_DATA},
+        crypto::{base64::Base64, Provider},
+        currency::WINSTONS_PER_AR,
+        error::Error,
+        transaction::{
+            detect_content_type,
+            tags::{FromUtf8Strs, Tag},
+            Tx,
+        },
+        ArweaveSigner,
+    };
+
+    #[test]
+    fn test_json_post_size() -> Result<(), Error> {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap())?;
+        assert_eq!(
+            tx.json_post_size(),
+            serde_json::to_vec(&tx).unwrap().len() as u64
+        );
+
+        let mut tx = tx;
+        tx.tags = vec![];
+        assert_eq!(
+            tx.json_post_size(),
+            serde_json::to_vec(&tx).unwrap().len() as u64
+        );
+
+        tx.tags = vec![
+            Tag::<Base64>::from_utf8_strs("Content-Type", "image/png")?,
+            Tag::<Base64>::from_utf8_strs("a", "")?,
+        ];
+        tx.data = Base64(vec![7; 1001]);
+        tx.data_size = 1001;
+        assert_eq!(
+            tx.json_post_size(),
+            serde_json::to_vec(&tx).unwrap().len() as u64
+        );
+        assert!(tx.json_post_size() > 1001 * 4 / 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_signature_data_matches_arweave_js() -> Result<(), Error> {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap())?;
+        // `getSignatureData` of the same transaction as calculated by arweave-js.
+        let expected =
+            Base64::from_str("Sg9K__jNL-Vrw0VM1_kiusUfsqNINk6zE7IBhLfng9WSywZjaufXx7WrNP_NN8t1")
+                .unwrap();
+        assert_eq!(tx.signature_data()?, expected.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunking_decision() {
+        let tx = |data_size| Tx {
+            data_size,
+            ..Default::default()
+        };
+        // 38 full chunks and a last one above MIN_CHUNK_SIZE either way.
+        assert!(!tx(MAX_TX_DATA).will_chunk());
+        assert_eq!(tx(MAX_TX_DATA).chunk_count(), 39);
+        assert!(tx(MAX_TX_DATA + 1).will_chunk());
+        assert_eq!(tx(MAX_TX_DATA + 1).chunk_count(), 39);
+        assert_eq!(tx(0).chunk_count(), 0);
+
+        let crypto = Provider::from_keypair_path(PathBuf::from("res/test_wallet.json")).unwrap();
+        let built = Tx::new(
+            &crypto,
+            Base64::empty(),
+            vec![1; 3 * 256 * 1024 + 10],
+            0,
+            0,
+            Base64::empty(),
+            vec![],
+            false,
+        )
+        .unwrap();
+        assert_eq!(built.chunk_count(), built.chunks.len() as u64);
+    }
+
+    #[test]
+    fn test_detect_content_type() {
+        let cases: [(&[u8], &str); 9] = [
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "image/png"),
+            (b"\xff\xd8\xff\xe0\0\x10JFIF\0", "image/jpeg"),
+            (b"%PDF-1.7\n", "application/pdf"),
+            (b"\x1f\x8b\x08\0\0\0\0\0", "application/gzip"),
+            (b" {\"name\": \"arweave-rs\"}\n", "application/json"),
+            (b"[1, 2, 3]", "application/json"),
+            (b"{not json", "text/plain"),
+            (b"hello, arweave\n", "text/plain"),
+            (b"\0\x01\x02\xfe", "application/octet-stream"),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(detect_content_type(data), expected, "{:?}", data);
+        }
+        assert_eq!(detect_content_type(b""), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_auto_content_tag_keeps_callers_content_type() {
+        let crypto = Provider::from_keypair_path(PathBuf::from("res/test_wallet.json")).unwrap();
+        let content_types = |other_tags| {
+            Tx::new(
+                &crypto,
+                Base64::empty(),
+                b"{\"a\": 1}".to_vec(),
+                0,
+                0,
+                Base64::empty(),
+                other_tags,
+                true,
+            )
+            .unwrap()
+            .tags
+            .into_iter()
+            .filter(|tag| tag.name.0.eq_ignore_ascii_case(b"Content-Type"))
+            .map(|tag| tag.value.to_utf8_string().unwrap())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(content_types(vec![]), ["application/json"]);
+        assert_eq!(
+            content_types(vec![Tag::<Base64>::from_utf8_strs(
+                "content-type",
+                "text/csv"
+            )
+            .unwrap()]),
+            ["text/csv"]
+        );
+    }
+
+    #[test]
+    fn test_tag_accessors() {
+        let tx = Tx {
+            tags: vec![
+                Tag::<Base64>::from_utf8_strs("Content-Type", "text/plain").unwrap(),
+                Tag {
+                    name: Base64(vec![0xff, 0xfe]),
+                    value: Base64(b"binary name".to_vec()),
+                },
+                Tag {
+                    name: Base64(b"Binary-Value".to_vec()),
+                    value: Base64(vec![0xc3, 0x28]),
+                },
+                Tag::<Base64>::from_utf8_strs("App-Name", "arweave-rs").unwrap(),
+                Tag::<Base64>::from_utf8_strs("App-Name", "other").unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(tx.get_tag("Content-Type").as_deref(), Some("text/plain"));
+        assert_eq!(tx.get_tag("App-Name").as_deref(), Some("arweave-rs"));
+        assert_eq!(tx.get_tag("content-type").as_deref(), Some("text/plain"));
+        assert_eq!(tx.get_tag("Binary-Value"), None);
+        assert_eq!(tx.get_tag("Missing"), None);
+        assert_eq!(
+            tx.tags_as_strings(),
+            [
+                ("Content-Type", "text/plain"),
+                ("App-Name", "arweave-rs"),
+                ("App-Name", "other")
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_content_digest_is_stable() -> Result<(), Error> {
+        let crypto = Provider::from_keypair_path(PathBuf::from("res/test_wallet.json")).unwrap();
+        let build = || {
+            Tx::new(
+                &crypto,
+                Base64(vec![1; 32]),
+                b"content".to_vec(),
+                5,
+                10,
+                Base64(vec![2; 32]),
+                vec![Tag::<Base64>::from_utf8_strs("App-Name", "arweave-rs").unwrap()],
+                true,
+            )
+            .unwrap()
+        };
+
+        let digest = build().content_digest()?;
+        assert_eq!(digest, build().content_digest()?);
+        assert_eq!(digest.0.len(), 32);
+
+        let mut other = build();
+        other.reward += 1;
+        assert_ne!(digest, other.content_digest()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gateway_url() -> Result<(), Error> {
+        let tx = Tx::from_str(&fs::read_to_string("res/sample_tx.json").unwrap())?;
+        let base_url = url::Url::parse(ARWEAVE_BASE_URL).unwrap();
+        assert_eq!(
+            tx.gateway_url(&base_url)?.as_str(),
+            "https://arweave.net/t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU"
+        );
+
+        let no_slash = url::Url::parse("https://example.com/gateway").unwrap();
+        assert_eq!(
+            tx.gateway_url(&no_slash)?.as_str(),
+            "https://example.com/gateway/t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU"
+        );
+
+        assert!(matches!(
+            Tx::default().gateway_url(&base_url),
+            Err(Error::UnsignedTransaction)
+        ));
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_chunks_reassemble_data(data_size in 1usize..(2 * 1024 * 1024)) {
+            let data: Vec<u8> = (0..data_size).map(|i| i as u8).collect();
+            let mut tx = Tx::generate_merkle(&data).unwrap();
+            tx.data = Base64(data.clone());
+
+            let mut reassembled = Vec::with_capacity(data_size);
+            for i in 0..tx.chunks.len() {
+                let chunk = tx.get_chunk(i).unwrap();
+                prop_assert_eq!(chunk.offset, tx.chunks[i].max_byte_range - 1);
+                prop_assert_eq!(tx.chunks[i].min_byte_range, reassembled.len());
+                reassembled.extend(chunk.chunk.0);
+            }
+            prop_assert_eq!(reassembled, data);
+            prop_assert!(tx.get_chunk(tx.chunks.len()).is_err());
+        }
+    }
+
+    fn arb_tag() -> impl Strategy<Value = Tag<Base64>> {
+        (
+            prop::collection::vec(any::<u8>(), 1..32),
+            prop::collection::vec(any::<u8>(), 0..64),
+        )
+            .prop_map(|(name, value)| Tag {
+                name: Base64(name),
+                value: Base64(value),
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(24))]
+
+        #[test]
+        fn test_tx_invariants(
+            tags in prop::collection::vec(arb_tag(), 0..8),
+            data in prop::collection::vec(any::<u8>(), 0..(300 * 1024)),
+            quantity in 0..(u64::MAX as u128 * WINSTONS_PER_AR as u128),
+            reward in any::<u64>(),
+            target in prop::option::of(prop::collection::vec(any::<u8>(), 32)),
+        ) {
+            let signer = ArweaveSigner::default();
+            let tx = Tx::new(
+                signer.get_provider(),
+                Base64(target.unwrap_or_default()),
+                data,
+                quantity,
+                reward,
+                Base64(vec![3; 32]),
+                tags,
+                false,
+            )
+            .unwrap();
+
+            // The deep hash is stable.
+            let digest = tx.content_digest().unwrap();
+            prop_assert_eq!(&digest, &tx.content_digest().unwrap());
+
+            // Sign -> verify.
+            let signed = signer.sign_transaction(tx).unwrap();
+            prop_assert!(ArweaveSigner::verify_transaction(&signed).is_ok());
+            prop_assert_eq!(&digest, &signed.content_digest().unwrap());
+
+            // Serialize -> parse, through both the gateway parser and the serde derive.
+            let json = serde_json::to_string(&signed).unwrap();
+            for parsed in [Tx::from_str(&json).unwrap(), serde_json::from_str(&json).unwrap()] {
+                prop_assert_eq!(serde_json::to_string(&parsed).unwrap(), json.clone());
+                prop_assert_eq!(&digest, &parsed.content_digest().unwrap());
+                prop_assert!(ArweaveSigner::verify_transaction(&parsed).is_ok());
+            }
+        }
+    }
+}
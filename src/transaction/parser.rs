@@ -7,11 +7,14 @@ use crate::{currency::Currency, error::Error};
 use super::{tags::Tag, Tx};
 use crate::types::Tx as JsonTx;
 
-impl From<JsonTx> for Tx {
-    fn from(json_tx: JsonTx) -> Self {
+impl TryFrom<JsonTx> for Tx {
+    type Error = Error;
+
+    fn try_from(json_tx: JsonTx) -> Result<Self, Self::Error> {
         let tags = json_tx.tags.iter().map(Tag::from).collect();
-        Tx {
-            quantity: Currency::from_str(&json_tx.quantity).unwrap(),
+        let parse_u64 = |s: &str| u64::from_str(s).map_err(|_| Error::InvalidAmount(s.to_string()));
+        Ok(Tx {
+            quantity: Currency::from_str(&json_tx.quantity)?,
             format: json_tx.format,
             id: json_tx.id,
             last_tx: json_tx.last_tx,
@@ -20,12 +23,13 @@ impl From<JsonTx> for Tx {
             target: json_tx.target,
             data_root: json_tx.data_root,
             data: json_tx.data,
-            data_size: u64::from_str(&json_tx.data_size).unwrap(),
-            reward: u64::from_str(&json_tx.reward).unwrap(),
+            data_size: parse_u64(&json_tx.data_size)?,
+            reward: parse_u64(&json_tx.reward)?,
             signature: json_tx.signature,
             chunks: vec![],
             proofs: vec![],
-        }
+            posted: Default::default(),
+        })
     }
 }
 
@@ -33,8 +37,9 @@ impl FromStr for Tx {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let json_tx: JsonTx = serde_json::from_str(s).expect("Could not parse json");
-        Ok(Tx::from(json_tx))
+        let json_tx: JsonTx =
+            serde_json::from_str(s).map_err(|e| Error::InvalidTxJson(e.to_string()))?;
+        Tx::try_from(json_tx)
     }
 }
 
@@ -63,12 +68,15 @@ impl Serialize for Tx {
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::File, io::Read, str::FromStr};
+    use std::{fs, fs::File, io::Read, str::FromStr};
+
+    use serde_json::json;
 
     use crate::{
         crypto::base64::Base64,
         currency::Currency,
-        transaction::{tags::Tag, Tx},
+        error::Error,
+        transaction::{tags::Tag, Tx, TxFormat},
     };
 
     #[test]
@@ -79,7 +87,7 @@ mod tests {
 
         let actual_tx = Tx::from_str(&data).unwrap();
         let expected_tx = Tx {
-            format: 2,
+            format: TxFormat::V2,
             id: Base64::from_str("t3K1b8IhvtGWxAGsipZE5NafmEGrtj3OAcYikJ0edeU").unwrap(),
             last_tx: Base64::from_str("ddvXNxatQmS3LeKi_x1RJn6g9G0esUaTEgT40a6f_WYyawZaSK3w8WC2czAuLgmT").unwrap(),
             owner: Base64::from_str("pjdss8ZaDfEH6K6U7GeW2nxDqR4IP049fk1fK0lndimbMMVBdPv_hSpm8T8EtBDxrUdi1OHZfMhUixGaut-3nQ4GG9nM249oxhCtxqqNvEXrmQRGqczyLxuh-fKn9Fg--hS9UpazHpfVAFnB5aCfXoNhPuI8oByyFKMKaOVgHNqP5NBEqabiLftZD3W_lsFCPGuzr4Vp0YS7zS2hDYScC2oOMu4rGU1LcMZf39p3153Cq7bS2Xh6Y-vw5pwzFYZdjQxDn8x8BG3fJ6j8TGLXQsbKH1218_HcUJRvMwdpbUQG5nvA2GXVqLqdwp054Lzk9_B_f1lVrmOKuHjTNHq48w").unwrap(),
@@ -94,9 +102,69 @@ mod tests {
             reward: 600912,
             signature: Base64::from_str("EJQN0DpfPBm1aUo1qk6dCkrY_zKHMJBQx3v36UOzmodF39RvBI2rqx_gTgLzszNkHIWnf-zwzXCz6xF5wzlrHWkosgfSwfZOhm3aVE5KLGvqVqSlMTlIzkIcR6KKFRe9m7HyOxJHvXykAD8X1X_6RExnXAZX4B9mwR10lqCG2wkRMJxchVisOZph-O5OfgteC1lb5YFx0BNAtmVgtUlY7dQdV1vVYq2_sDJPkYpHK5YIMIjoRsqdGP31gOFXTmzuIHYhRyii-clx2uxrv0pjfnv9tl9WPViHu3FGLlW9tH5z3mXdt7PQx-o8MGK_MXz10LLlqsPdos2rI3D3MgPUqQ").unwrap(),
             chunks: vec![],
-            proofs: vec![]
+            proofs: vec![],
+            posted: Default::default(),
         };
 
         assert_eq!(actual_tx, expected_tx);
     }
+
+    #[test]
+    fn test_serde_round_trip_stringifies_numbers() {
+        let data = fs::read_to_string("res/sample_tx.json").unwrap();
+        let tx = Tx::from_str(&data).unwrap();
+
+        let value = serde_json::to_value(&tx).unwrap();
+        assert_eq!(value["quantity"], json!("100000"));
+        assert_eq!(value["data_size"], json!("0"));
+        assert_eq!(value["reward"], json!("600912"));
+
+        let round_trip: Tx = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip, tx);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let sample_tx: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+
+        for (format, expected) in [(1, TxFormat::V1), (2, TxFormat::V2)] {
+            let mut json_tx = sample_tx.clone();
+            json_tx["format"] = json!(format);
+            let tx = Tx::from_str(&json_tx.to_string()).unwrap();
+            assert_eq!(tx.format, expected);
+            assert_eq!(serde_json::to_value(&tx).unwrap()["format"], json!(format));
+        }
+
+        let mut json_tx = sample_tx;
+        json_tx["format"] = json!(3);
+        assert!(matches!(
+            Tx::from_str(&json_tx.to_string()),
+            Err(Error::InvalidTxJson(message)) if message.contains("format: 3")
+        ));
+        assert!(matches!(
+            TxFormat::try_from(0),
+            Err(Error::InvalidTxFormat(0))
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_amounts() {
+        let sample_tx: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string("res/sample_tx.json").unwrap()).unwrap();
+
+        for (field, value) in [
+            ("quantity", "1.5.0"),
+            ("quantity", "not a number"),
+            ("reward", "-1"),
+            ("data_size", "18446744073709551616"),
+        ] {
+            let mut json_tx = sample_tx.clone();
+            json_tx[field] = json!(value);
+            assert!(matches!(
+                Tx::from_str(&json_tx.to_string()),
+                Err(Error::InvalidAmount(amount)) if amount == value
+            ));
+        }
+    }
 }
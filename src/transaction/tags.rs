@@ -1,3 +1,5 @@
+use avro_rs::{from_avro_datum, to_avro_datum, types::Value, Schema};
+use lazy_static::lazy_static;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 use crate::{
@@ -8,6 +10,24 @@ use crate::{
 
 use super::ToItems;
 
+lazy_static! {
+    /// Avro schema used to encode tags in ANS-104 data items.
+    static ref AVRO_TAGS_SCHEMA: Schema = Schema::parse_str(
+        r#"{
+            "type": "array",
+            "items": {
+                "type": "record",
+                "name": "Tag",
+                "fields": [
+                    { "name": "name", "type": "bytes" },
+                    { "name": "value", "type": "bytes" }
+                ]
+            }
+        }"#
+    )
+    .unwrap();
+}
+
 /// Transaction tag.
 #[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Tag<T> {
@@ -15,6 +35,41 @@ pub struct Tag<T> {
     pub value: T,
 }
 
+/// How [`TagSet::canonicalize`] rewrites a list of tags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanonMode {
+    /// Removes tags with the same name and value as an earlier tag, keeping the first.
+    Dedup,
+    /// Sorts tags by name bytes. Tags with the same name keep their relative order.
+    SortByName,
+}
+
+/// Canonical forms of a list of tags, for indexers that expect them.
+///
+/// Tag order is committed in the signature, so canonicalize the tags of a transaction before
+/// signing it. Applying it afterwards can change the tags and invalidate the signature.
+pub trait TagSet {
+    fn canonicalize(&mut self, mode: CanonMode);
+}
+
+impl TagSet for Vec<Tag<Base64>> {
+    fn canonicalize(&mut self, mode: CanonMode) {
+        match mode {
+            CanonMode::Dedup => {
+                let mut seen = Vec::with_capacity(self.len());
+                self.retain(|tag| {
+                    if seen.contains(tag) {
+                        return false;
+                    }
+                    seen.push(tag.clone());
+                    true
+                });
+            }
+            CanonMode::SortByName => self.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+    }
+}
+
 /// Implemented to create [`Tag`]s from utf-8 strings.
 pub trait FromUtf8Strs<T> {
     fn from_utf8_strs(name: &str, value: &str) -> Result<T, Error>;
@@ -41,12 +96,63 @@ impl FromUtf8Strs<Tag<String>> for Tag<String> {
     }
 }
 
+impl Tag<Base64> {
+    /// Encodes tags in the Avro format used by [ANS-104](https://github.com/ArweaveTeam/arweave-standards/blob/master/ans/ANS-104.md) data items.
+    /// No tags encode to no bytes, not to an empty Avro array, as in data items without tags.
+    pub fn to_avro_bytes(tags: &[Tag<Base64>]) -> Result<Vec<u8>, Error> {
+        if tags.is_empty() {
+            return Ok(Vec::new());
+        }
+        let value = Value::Array(
+            tags.iter()
+                .map(|t| {
+                    Value::Record(vec![
+                        ("name".to_string(), Value::Bytes(t.name.0.clone())),
+                        ("value".to_string(), Value::Bytes(t.value.0.clone())),
+                    ])
+                })
+                .collect(),
+        );
+        to_avro_datum(&AVRO_TAGS_SCHEMA, value).map_err(|_| Error::InvalidTagEncoding)
+    }
+
+    /// Decodes tags encoded with [`Tag::to_avro_bytes`]. No bytes decode to no tags.
+    pub fn from_avro_bytes(mut bytes: &[u8]) -> Result<Vec<Tag<Base64>>, Error> {
+        if bytes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let value = from_avro_datum(&AVRO_TAGS_SCHEMA, &mut bytes, None)
+            .map_err(|_| Error::InvalidTagEncoding)?;
+        if !bytes.is_empty() {
+            return Err(Error::InvalidTagEncoding);
+        }
+
+        let items = match value {
+            Value::Array(items) => items,
+            _ => return Err(Error::InvalidTagEncoding),
+        };
+        items
+            .into_iter()
+            .map(|item| match item {
+                Value::Record(fields) => match <[(String, Value); 2]>::try_from(fields) {
+                    Ok([(_, Value::Bytes(name)), (_, Value::Bytes(value))]) => Ok(Tag {
+                        name: Base64(name),
+                        value: Base64(value),
+                    }),
+                    _ => Err(Error::InvalidTagEncoding),
+                },
+                _ => Err(Error::InvalidTagEncoding),
+            })
+            .collect()
+    }
+}
+
 impl<'a> ToItems<'a, Vec<Tag<Base64>>> for Vec<Tag<Base64>> {
     fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
         Ok(DeepHashItem::List(
             self.iter()
-                .map(|t| t.to_deep_hash_item().unwrap())
-                .collect(),
+                .map(|t| t.to_deep_hash_item())
+                .collect::<Result<_, _>>()?,
         ))
     }
 }
@@ -81,3 +187,187 @@ impl From<&BaseTag> for Tag<Base64> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use crate::{
+        crypto::{
+            base64::Base64,
+            hash::{deep_hash, ToItems},
+            Provider,
+        },
+        currency::Currency,
+        error::Error,
+        signer::ArweaveSigner,
+        transaction::{
+            tags::{CanonMode, FromUtf8Strs, Tag, TagSet},
+            Tx, TxFormat,
+        },
+    };
+
+    #[test]
+    fn test_tag_order_committed_in_deep_hash() -> Result<(), Error> {
+        let signer = ArweaveSigner::default();
+        let tags = vec![
+            Tag::<Base64>::from_utf8_strs("Zeta", "1")?,
+            Tag::<Base64>::from_utf8_strs("Alpha", "2")?,
+            Tag::<Base64>::from_utf8_strs("Mid", "3")?,
+        ];
+        let tx = Tx::new(
+            signer.get_provider(),
+            Base64::empty(),
+            b"tag order".to_vec(),
+            0,
+            0,
+            Base64::empty(),
+            tags,
+            false,
+        )?;
+        let signed_tx = signer.sign_transaction(tx)?;
+        let hash = deep_hash(signed_tx.to_deep_hash_item()?);
+
+        // Tags survive a serialize / parse round trip in insertion order.
+        let json = serde_json::to_string(&signed_tx).unwrap();
+        let parsed_tx = Tx::from_str(&json)?;
+        assert_eq!(parsed_tx.tags, signed_tx.tags);
+        assert_eq!(deep_hash(parsed_tx.to_deep_hash_item()?), hash);
+        assert!(ArweaveSigner::verify_transaction(&parsed_tx).is_ok());
+
+        // Reordering the tags changes the signed message, so the signature no longer verifies.
+        let mut reordered_tx = Tx::from_str(&json)?;
+        reordered_tx.tags.swap(0, 1);
+        assert_ne!(deep_hash(reordered_tx.to_deep_hash_item()?), hash);
+        assert!(ArweaveSigner::verify_transaction(&reordered_tx).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonicalize_before_signing() -> Result<(), Error> {
+        let tag = Tag::<Base64>::from_utf8_strs;
+        let tags = vec![
+            tag("Zeta", "1")?,
+            tag("Alpha", "2")?,
+            tag("Zeta", "1")?,
+            tag("Alpha", "3")?,
+        ];
+        let tx_with_tags = |tags: Vec<Tag<Base64>>| {
+            Tx::new(
+                &Provider::default(),
+                Base64::empty(),
+                b"canonical".to_vec(),
+                0,
+                0,
+                Base64::empty(),
+                tags,
+                false,
+            )
+        };
+        let hash_of =
+            |tx: &Tx| -> Result<[u8; 48], Error> { Ok(deep_hash(tx.to_deep_hash_item()?)) };
+
+        let mut deduped = tags.clone();
+        deduped.canonicalize(CanonMode::Dedup);
+        assert_eq!(
+            deduped,
+            vec![tag("Zeta", "1")?, tag("Alpha", "2")?, tag("Alpha", "3")?]
+        );
+
+        let mut sorted = deduped.clone();
+        sorted.canonicalize(CanonMode::SortByName);
+        assert_eq!(
+            sorted,
+            vec![tag("Alpha", "2")?, tag("Alpha", "3")?, tag("Zeta", "1")?]
+        );
+
+        let original = hash_of(&tx_with_tags(tags)?)?;
+        let deduped_hash = hash_of(&tx_with_tags(deduped)?)?;
+        let sorted_hash = hash_of(&tx_with_tags(sorted.clone())?)?;
+        assert_ne!(original, deduped_hash);
+        assert_ne!(deduped_hash, sorted_hash);
+
+        // Sorting already canonical tags leaves the deep hash unchanged.
+        let mut resorted = sorted.clone();
+        resorted.canonicalize(CanonMode::SortByName);
+        assert_eq!(hash_of(&tx_with_tags(resorted)?)?, sorted_hash);
+        Ok(())
+    }
+
+    #[test]
+    fn test_avro_round_trip() -> Result<(), Error> {
+        let tags = vec![
+            Tag::<Base64>::from_utf8_strs("Content-Type", "text/plain")?,
+            Tag::<Base64>::from_utf8_strs("App-Name", "arweave-rs")?,
+        ];
+        let bytes = Tag::to_avro_bytes(&tags)?;
+
+        // Avro binary layout from the Avro spec and ANS-104: zigzag encoded item count, zigzag
+        // length prefixed name and value for each tag and a zero terminating the block.
+        let expected = [
+            &[4, 24][..],
+            b"Content-Type",
+            &[20],
+            b"text/plain",
+            &[16],
+            b"App-Name",
+            &[20],
+            b"arweave-rs",
+            &[0],
+        ]
+        .concat();
+        assert_eq!(bytes, expected);
+        assert_eq!(Tag::from_avro_bytes(&bytes)?, tags);
+
+        assert_eq!(Tag::to_avro_bytes(&[])?, Vec::<u8>::new());
+        assert_eq!(Tag::from_avro_bytes(&[])?, vec![]);
+        assert_eq!(Tag::from_avro_bytes(&[0])?, vec![]);
+        assert!(matches!(
+            Tag::from_avro_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidTagEncoding)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tags_deep_hash_matches_arweave_js() -> Result<(), Error> {
+        // Vectors computed with arweave-js' `deepHash` and format 2 `getSignatureData` logic,
+        // run under node over the fields of this transaction.
+        let crypto =
+            Provider::from_keypair_path(PathBuf::from_str("res/test_wallet.json").unwrap())
+                .unwrap();
+        let tags = [
+            ("Content-Type", "text/plain"),
+            ("Empty-Value", ""),
+            ("名前", "値"),
+            ("", "empty name"),
+            ("App-Name", "arweave-rs"),
+        ]
+        .into_iter()
+        .map(|(name, value)| Tag::<Base64>::from_utf8_strs(name, value))
+        .collect::<Result<Vec<_>, Error>>()?;
+        let tx = Tx {
+            format: TxFormat::V2,
+            owner: crypto.keypair_modulus(),
+            target: Base64(vec![7; 32]),
+            quantity: Currency::from(1_500_000_000_000),
+            reward: 600912,
+            last_tx: Base64(vec![9; 48]),
+            tags,
+            data_size: 11,
+            data_root: Base64(vec![3; 32]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Base64(deep_hash(tx.tags.to_deep_hash_item()?).to_vec()).to_string(),
+            "pc0uuyPIVTpiHwSrulthuMquh6-RtzPkqgUPJPd0ZeEevfl9wlm-mooUxXMWGoF5"
+        );
+        assert_eq!(
+            Base64(deep_hash(tx.to_deep_hash_item()?).to_vec()).to_string(),
+            "xciBAS9pDvf958-q9WmmPtmGJuwpyhNVjmuiUZ4z7KATHNJiyQcz8PVPeiIVs7ju"
+        );
+        Ok(())
+    }
+}
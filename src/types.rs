@@ -1,7 +1,14 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_aux::prelude::*;
 
-use crate::crypto::base64::Base64;
+use crate::{crypto::base64::Base64, transaction::TxFormat};
+
+fn serialize_to_string<T: ToString, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NetworkInfo {
@@ -16,6 +23,13 @@ pub struct NetworkInfo {
     pub node_state_latency: usize,
 }
 
+/// ArNS record as returned by a gateway's `/ar-io/resolver/records/{name}` endpoint.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ArnsRecord {
+    pub tx_id: Base64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProofOfAccess {
     pub option: String,
@@ -55,7 +69,7 @@ pub struct BlockInfo {
 }
 #[derive(Deserialize, Debug, Default, Eq, PartialEq)]
 pub struct Tx {
-    pub format: u8,
+    pub format: TxFormat,
     pub id: Base64,
     pub last_tx: Base64,
     pub owner: Base64,
@@ -75,18 +89,109 @@ pub struct Tag {
     pub value: Base64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct TxStatus {
-    pub block_height: u128,
+    pub block_height: u64,
     pub block_indep_hash: Base64,
     pub number_of_confirmations: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+/// Whether a posted transaction has been mined, from `GET /tx/{id}/status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Accepted by the gateway but not yet in a block.
+    Pending,
+    Confirmed(TxStatus),
+}
+
+/// Location of a transaction's data in the weave, from `GET /tx/{id}/offset`. `offset` is the
+/// absolute offset of the last byte.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TxOffset {
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub offset: u64,
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub size: u64,
+}
+
+/// Response to a GraphQL `transactions` query selecting `edges { node { id } }`.
+#[derive(Deserialize, Debug)]
+pub struct TransactionsQueryResponse {
+    pub data: TransactionsQueryData,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransactionsQueryData {
+    pub transactions: TransactionConnection,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransactionConnection {
+    pub edges: Vec<TransactionEdge>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransactionEdge {
+    pub node: TransactionNode,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransactionNode {
+    pub id: Base64,
+}
+
+/// How many of a transaction's chunks a gateway has accepted, from
+/// `GET /tx/{id}/pending_chunks` on gateways that support it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ChunkUploadStatus {
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub accepted: u64,
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub total: u64,
+}
+
+impl ChunkUploadStatus {
+    pub fn is_complete(&self) -> bool {
+        self.accepted >= self.total
+    }
+}
+
+/// Chunk returned by `GET /chunk/{offset}`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChunkData {
+    pub chunk: Base64,
+    pub data_path: Base64,
+    #[serde(default)]
+    pub tx_path: Base64,
+}
+
+/// Body of a `POST /chunk` request. Chunks are always posted unpacked; since 2.6 the node packs
+/// them itself. `data_size` and `offset` are sent as strings, like arweave-js does.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
 pub struct Chunk {
     pub data_root: Base64,
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
     pub data_size: u64,
     pub data_path: Base64,
+    #[serde(
+        serialize_with = "serialize_to_string",
+        deserialize_with = "deserialize_number_from_string"
+    )]
     pub offset: usize,
     pub chunk: Base64,
 }
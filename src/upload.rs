@@ -1,4 +1,4 @@
-use std::{str::FromStr, thread::sleep, time::Duration};
+use std::{str::FromStr, sync::Arc};
 
 use reqwest::{
     header::{ACCEPT, CONTENT_TYPE},
@@ -6,25 +6,86 @@ use reqwest::{
 };
 
 use crate::{
-    consts::{ARWEAVE_BASE_URL, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+    clock::{Clock, SystemClock},
+    consts::{ARWEAVE_BASE_URL, CHUNKS_BUFFER_FACTOR},
     error::Error,
+    http::HttpError,
+    retry::RetryPolicy,
     types::Chunk,
 };
 
+/// Outcome of a chunk upload that was cancelled, or stopped by a failed chunk, before every
+/// chunk was posted. Posting the chunks at `remaining` resumes the upload.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartialUpload {
+    pub id: String,
+    pub completed: Vec<usize>,
+    pub remaining: Vec<usize>,
+}
+
+impl PartialUpload {
+    pub fn is_complete(&self) -> bool {
+        self.remaining.is_empty()
+    }
+}
+
+/// Order in which chunks are posted once the transaction header has been accepted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkOrder {
+    /// Posts several chunks at once, so they can arrive in any order.
+    #[default]
+    Concurrent,
+    /// Posts one chunk at a time in increasing offset order, for gateways that won't serve the
+    /// data while there are gaps in it.
+    Sequential,
+}
+
+impl ChunkOrder {
+    /// Number of chunks posted at once given the requested `buffer`.
+    pub fn concurrency(&self, buffer: usize) -> usize {
+        match self {
+            ChunkOrder::Concurrent => buffer.max(1),
+            ChunkOrder::Sequential => 1,
+        }
+    }
+
+    /// Number of chunks posted at once for a `buffer` argument, which allows
+    /// `buffer * CHUNKS_BUFFER_FACTOR` requests in flight.
+    pub fn buffered_concurrency(&self, buffer: usize) -> usize {
+        self.concurrency(buffer.saturating_mul(CHUNKS_BUFFER_FACTOR))
+    }
+}
+
 pub struct Uploader {
     url: url::Url,
+    clock: Arc<dyn Clock>,
+    retry_policy: RetryPolicy,
 }
 
 impl Default for Uploader {
     fn default() -> Self {
         let url = url::Url::from_str(ARWEAVE_BASE_URL).unwrap();
-        Self { url }
+        Self::new(url)
     }
 }
 
 impl Uploader {
     pub fn new(url: url::Url) -> Self {
-        Uploader { url }
+        Uploader {
+            url,
+            clock: Arc::new(SystemClock),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
     pub async fn post_chunk_with_retries(
@@ -35,12 +96,13 @@ impl Uploader {
         let mut retries = 0;
         let mut resp = self.post_chunk(&chunk, &client).await;
 
-        while retries < CHUNKS_RETRIES {
+        while retries < self.retry_policy.max_retries {
             match resp {
                 Ok(offset) => return Ok(offset),
+                Err(Error::ChunkRejected(reason)) => return Err(Error::ChunkRejected(reason)),
                 Err(e) => {
                     dbg!("post_chunk_with_retries: {:?}", e);
-                    sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP));
+                    self.clock.sleep(self.retry_policy.delay(retries)).await;
                     retries += 1;
                     resp = self.post_chunk(&chunk, &client).await;
                 }
@@ -51,7 +113,6 @@ impl Uploader {
 
     pub async fn post_chunk(&self, chunk: &Chunk, client: &Client) -> Result<usize, Error> {
         let url = self.url.join("chunk").expect("Could not join url");
-        // let client = reqwest::Client::new();
 
         let resp = client
             .post(url)
@@ -60,11 +121,188 @@ impl Uploader {
             .header(&CONTENT_TYPE, "application/json")
             .send()
             .await
-            .map_err(|e| Error::PostChunkError(e.to_string()))?;
+            .map_err(|e| HttpError::from(e).into_error(Error::PostChunkError))?;
 
         match resp.status() {
-            reqwest::StatusCode::OK => Ok(chunk.offset),
-            _ => Err(Error::StatusCodeNotOk),
+            // 208 means the node already has the chunk.
+            reqwest::StatusCode::OK | reqwest::StatusCode::ALREADY_REPORTED => Ok(chunk.offset),
+            // The node rejected the chunk itself (e.g. an invalid proof), so retrying won't help.
+            // A timeout or throttling is only a reason to try again later.
+            status
+                if status.is_client_error()
+                    && status != reqwest::StatusCode::REQUEST_TIMEOUT
+                    && status != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+            {
+                Err(Error::ChunkRejected(
+                    resp.text().await.unwrap_or_else(|_| status.to_string()),
+                ))
+            }
+            status => Err(Error::StatusCodeNotOk {
+                status: status.as_u16(),
+                body: resp.text().await.unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use async_trait::async_trait;
+    use httpmock::{Method::POST, Mock, MockServer};
+    use reqwest::Client;
+    use serde_json::json;
+    use tokio_test::block_on;
+
+    use crate::{
+        clock::{Clock, TestClock},
+        consts::{CHUNKS_BUFFER_FACTOR, CHUNKS_RETRIES, CHUNKS_RETRY_SLEEP},
+        crypto::base64::Base64,
+        error::Error,
+        types::Chunk,
+    };
+
+    use super::{ChunkOrder, Uploader};
+
+    #[test]
+    fn test_chunk_concurrency() {
+        assert_eq!(ChunkOrder::Concurrent.concurrency(0), 1);
+        assert_eq!(ChunkOrder::Concurrent.concurrency(3), 3);
+        assert_eq!(
+            ChunkOrder::Concurrent.buffered_concurrency(3),
+            3 * CHUNKS_BUFFER_FACTOR
+        );
+        assert_eq!(ChunkOrder::Concurrent.buffered_concurrency(0), 1);
+        assert_eq!(ChunkOrder::Sequential.buffered_concurrency(3), 1);
+    }
+
+    #[test]
+    fn test_post_chunk_retry_schedule() {
+        let server = MockServer::start();
+        let chunk_mock = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(500);
+        });
+        let clock = Arc::new(TestClock::new());
+        let uploader =
+            Uploader::new(url::Url::parse(&server.url("/")).unwrap()).with_clock(clock.clone());
+        let chunk = Chunk {
+            data_root: Base64::empty(),
+            data_size: 4,
+            data_path: Base64::empty(),
+            offset: 3,
+            chunk: Base64(b"test".to_vec()),
+        };
+
+        let res = block_on(uploader.post_chunk_with_retries(chunk, Client::new()));
+
+        assert!(matches!(
+            res,
+            Err(Error::StatusCodeNotOk { status: 500, .. })
+        ));
+        chunk_mock.assert_hits(CHUNKS_RETRIES as usize + 1);
+        let retry_sleep = Duration::from_secs(CHUNKS_RETRY_SLEEP);
+        assert_eq!(clock.sleeps(), vec![retry_sleep; CHUNKS_RETRIES as usize]);
+        assert_eq!(clock.elapsed(), retry_sleep * CHUNKS_RETRIES as u32);
+    }
+
+    /// Clock that, on the first sleep, replaces the mock with id `throttled` by one accepting
+    /// chunks, so the retry after the sleep succeeds.
+    struct ThrottledOnce {
+        server: Arc<MockServer>,
+        throttled: Mutex<Option<usize>>,
+    }
+
+    #[async_trait]
+    impl Clock for ThrottledOnce {
+        fn now(&self) -> Instant {
+            Instant::now()
         }
+
+        async fn sleep(&self, _duration: Duration) {
+            if let Some(id) = self.throttled.lock().unwrap().take() {
+                Mock::new(id, &self.server).delete();
+                self.server.mock(|when, then| {
+                    when.method(POST).path("/chunk");
+                    then.status(200);
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_post_chunk_retries_when_throttled() {
+        let server = Arc::new(MockServer::start());
+        let throttled = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(429);
+        });
+        let uploader = Uploader::new(url::Url::parse(&server.url("/")).unwrap()).with_clock(
+            Arc::new(ThrottledOnce {
+                server: server.clone(),
+                throttled: Mutex::new(Some(throttled.id)),
+            }),
+        );
+        let chunk = Chunk {
+            data_root: Base64::empty(),
+            data_size: 4,
+            data_path: Base64::empty(),
+            offset: 3,
+            chunk: Base64(b"test".to_vec()),
+        };
+
+        let res = block_on(uploader.post_chunk_with_retries(chunk, Client::new()));
+        assert_eq!(res.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_post_chunk_body_and_responses() {
+        let chunk = Chunk {
+            data_root: Base64(vec![1; 32]),
+            data_size: 262_145,
+            data_path: Base64(vec![2; 96]),
+            offset: 131_072,
+            chunk: Base64(b"test".to_vec()),
+        };
+        let body = json!({
+            "data_root": chunk.data_root.to_string(),
+            "data_size": "262145",
+            "data_path": chunk.data_path.to_string(),
+            "offset": "131072",
+            "chunk": "dGVzdA",
+        });
+        assert_eq!(serde_json::to_value(&chunk).unwrap(), body);
+        assert_eq!(
+            serde_json::from_value::<Chunk>(body.clone()).unwrap(),
+            chunk
+        );
+
+        let server = MockServer::start();
+        let uploader = Uploader::new(url::Url::parse(&server.url("/")).unwrap())
+            .with_clock(Arc::new(TestClock::new()));
+        let client = Client::new();
+
+        let mut already_stored = server.mock(|when, then| {
+            when.method(POST).path("/chunk").json_body(body.clone());
+            then.status(208);
+        });
+        let res = block_on(uploader.post_chunk(&chunk, &client));
+        assert_eq!(res.unwrap(), 131_072);
+        already_stored.assert();
+        already_stored.delete();
+
+        let rejected = server.mock(|when, then| {
+            when.method(POST).path("/chunk");
+            then.status(400).body(r#"{"error":"invalid_proof"}"#);
+        });
+        let res = block_on(uploader.post_chunk_with_retries(chunk, client));
+        assert!(
+            matches!(res, Err(Error::ChunkRejected(reason)) if reason.contains("invalid_proof"))
+        );
+        rejected.assert_hits(1);
     }
 }
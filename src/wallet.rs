@@ -1,7 +1,19 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use jsonwebkey::JsonWebKey;
 use pretend::{pretend, resolver::UrlResolver, Pretend, Url};
 use pretend_reqwest::Client as HttpClient;
 
-use crate::error::Error;
+use crate::{
+    crypto::{base64::Base64, Provider},
+    currency::Winston,
+    error::Error,
+    Arweave, ArweaveSigner,
+};
 
 #[pretend]
 trait TransactionInfoFetch {
@@ -16,8 +28,12 @@ pub struct WalletInfoClient(Pretend<HttpClient, UrlResolver>);
 
 impl WalletInfoClient {
     pub fn new(url: Url) -> Self {
-        let client = HttpClient::default();
-        let pretend = Pretend::for_client(client).with_url(url);
+        Self::with_client(url, reqwest::Client::new())
+    }
+
+    /// Sends requests through `client`, sharing its connection pool.
+    pub fn with_client(url: Url, client: reqwest::Client) -> Self {
+        let pretend = Pretend::for_client(HttpClient::new(client)).with_url(url);
         Self(pretend)
     }
 
@@ -36,13 +52,112 @@ impl WalletInfoClient {
     }
 }
 
+/// Keypair together with its address and the last balance fetched for it.
+pub struct Wallet {
+    crypto: Provider,
+    balance: Option<Winston>,
+}
+
+impl Wallet {
+    pub fn new(crypto: Provider) -> Self {
+        Self {
+            crypto,
+            balance: None,
+        }
+    }
+
+    pub fn from_keypair_path(keypair_path: PathBuf) -> Result<Self, Error> {
+        Ok(Self::new(Provider::from_keypair_path(keypair_path)?))
+    }
+
+    pub fn provider(&self) -> &Provider {
+        &self.crypto
+    }
+
+    pub fn address(&self) -> Base64 {
+        self.crypto.wallet_address()
+    }
+
+    pub fn public_key(&self) -> Base64 {
+        self.crypto.keypair_modulus()
+    }
+
+    /// Balance as of the last call to [`Wallet::refresh_balance`], if any.
+    pub fn balance(&self) -> Option<Winston> {
+        self.balance
+    }
+
+    /// Fetches the current balance from the gateway `arweave` is pointed at and caches it.
+    pub async fn refresh_balance(&mut self, arweave: &Arweave) -> Result<Winston, Error> {
+        let balance = arweave.get_wallet_balance(self.address()).await?;
+        self.balance = Some(balance);
+        Ok(balance)
+    }
+}
+
+/// Signers for several wallets, indexed by address, for services that sign on behalf of more
+/// than one wallet. Transactions take their owner from the provider they are created with, so
+/// create them with [`ArweaveSigner::get_provider`] of the same signer that signs them.
+#[derive(Default)]
+pub struct WalletStore {
+    signers: BTreeMap<Base64, ArweaveSigner>,
+}
+
+impl WalletStore {
+    /// Loads every `.json` keyfile in `dir`. Fails if any of them isn't a valid RSA keypair.
+    pub fn from_dir(dir: &Path) -> Result<Self, Error> {
+        let mut store = Self::default();
+        for entry in fs::read_dir(dir).map_err(|e| Error::IoError(e.to_string()))? {
+            let path = entry.map_err(|e| Error::IoError(e.to_string()))?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let jwk: JsonWebKey = fs::read_to_string(&path)
+                .map_err(|e| Error::IoError(e.to_string()))?
+                .parse()
+                .map_err(|e| Error::InvalidKeypair(format!("{}: {}", path.display(), e)))?;
+            store.insert(ArweaveSigner::new(Provider::try_from(jwk)?));
+        }
+        Ok(store)
+    }
+
+    /// Adds `signer`, replacing any signer already stored for its address.
+    pub fn insert(&mut self, signer: ArweaveSigner) {
+        self.signers.insert(signer.wallet_address(), signer);
+    }
+
+    pub fn signer_for(&self, address: &Base64) -> Option<&ArweaveSigner> {
+        self.signers.get(address)
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &Base64> {
+        self.signers.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.signers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{fs, path::PathBuf, str::FromStr};
+
     use httpmock::{Method::GET, MockServer};
     use pretend::Url;
     use tokio_test::block_on;
 
-    use crate::wallet::WalletInfoClient;
+    use crate::{
+        crypto::base64::Base64,
+        currency::Winston,
+        error::Error,
+        wallet::{Wallet, WalletInfoClient, WalletStore},
+        Arweave,
+    };
 
     #[test]
     fn test_balance() {
@@ -85,4 +200,66 @@ mod tests {
         mock.assert();
         assert_eq!(tx_info, "last_tx".to_string());
     }
+
+    #[test]
+    fn test_invalid_keyfile() {
+        assert!(matches!(
+            Wallet::from_keypair_path(PathBuf::from_str("res/network_info.json").unwrap()),
+            Err(Error::InvalidKeypair(_))
+        ));
+        assert!(matches!(
+            Wallet::from_keypair_path(PathBuf::from_str("res/missing.json").unwrap()),
+            Err(Error::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_wallet_refresh_balance() {
+        let path = PathBuf::from_str("res/test_wallet.json").unwrap();
+        let mut wallet = Wallet::from_keypair_path(path.clone()).unwrap();
+        let address = wallet.address().to_string();
+        assert_eq!(address, "ggHWyKn0I_CTtsyyt2OR85sPYz9OvKLd9DYIvRQ2ET4");
+        assert_eq!(wallet.balance(), None);
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/wallet/{}/balance", address));
+            then.status(200).body("123123");
+        });
+
+        let arweave =
+            Arweave::from_keypair_path(path, Url::parse(&server.url("/")).unwrap()).unwrap();
+        let balance = block_on(wallet.refresh_balance(&arweave)).unwrap();
+
+        mock.assert();
+        assert_eq!(balance, Winston(123123));
+        assert_eq!(wallet.balance(), Some(Winston(123123)));
+    }
+
+    #[test]
+    fn test_wallet_store_selects_by_address() {
+        let dir = std::env::temp_dir().join(format!("arweave-rs-wallets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let keyfiles = ["res/test_wallet.json", "res/test_wallet_4096.json"];
+        let mut addresses = vec![];
+        for (i, keyfile) in keyfiles.iter().enumerate() {
+            fs::copy(keyfile, dir.join(format!("wallet_{}.json", i))).unwrap();
+            let wallet = Wallet::from_keypair_path(PathBuf::from_str(keyfile).unwrap()).unwrap();
+            addresses.push(wallet.address());
+        }
+        fs::write(dir.join("README"), "not a keyfile").unwrap();
+
+        let store = WalletStore::from_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(store.len(), 2);
+        for address in &addresses {
+            assert_eq!(
+                &store.signer_for(address).unwrap().wallet_address(),
+                address
+            );
+        }
+        assert!(store.signer_for(&Base64(vec![0; 32])).is_none());
+    }
 }